@@ -0,0 +1,691 @@
+//! 容量计算：数据点插值、按输入合并的明细行与合计、推荐范围和负荷检查
+
+use std::collections::HashMap;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::data::{MachineData, MachineDatabase};
+use crate::error::LcError;
+
+/// 插值结果的来源，用于 --explain 和外推标记
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InterpolationKind {
+    /// 目标温度正好是一个数据点
+    Exact,
+    /// 在两个数据点之间线性插值
+    Interpolated,
+    /// 超出数据点范围，沿最近两点的斜率外推
+    Extrapolated,
+    /// 只有一个数据点，直接取该值
+    SinglePoint,
+}
+
+/// 一次插值的结果：取值、计算方式以及参与计算的两个数据点
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Interpolation {
+    pub value: f64,
+    pub kind: InterpolationKind,
+    // 参与计算的两个 (温度, Btu) 点
+    pub bracket: [(f64, f64); 2],
+}
+
+/// 在升序数据点上线性插值（超出范围时沿最近两点外推）
+pub fn interpolate_points(points: &[(f64, f64)], target_temp: f64) -> Option<Interpolation> {
+    if points.is_empty() { return None; }
+    if points.len() == 1 {
+        return Some(Interpolation { value: points[0].1, kind: InterpolationKind::SinglePoint, bracket: [points[0], points[0]] });
+    }
+
+    let (p1, p2) = if target_temp <= points[0].0 {
+        (points[0], points[1])
+    } else if target_temp >= points.last().unwrap().0 {
+        let len = points.len();
+        (points[len-2], points[len-1])
+    } else {
+        let mut found = (points[0], points[1]);
+        for window in points.windows(2) {
+            if target_temp >= window[0].0 && target_temp <= window[1].0 {
+                found = (window[0], window[1]);
+                break;
+            }
+        }
+        found
+    };
+
+    let kind = if target_temp < points[0].0 || target_temp > points.last().unwrap().0 {
+        InterpolationKind::Extrapolated
+    } else {
+        InterpolationKind::Interpolated
+    };
+
+    let (x1, y1) = p1;
+    let (x2, y2) = p2;
+    
+    let value = if (x2 - x1).abs() < 1e-6 {
+        y1
+    } else {
+        let slope = (y2 - y1) / (x2 - x1);
+        y1 + (target_temp - x1) * slope
+    };
+    Some(Interpolation { value, kind, bracket: [p1, p2] })
+}
+
+/// 整个机器清单的合计（各温度点的总制热量、加权 HSPF/SEER/EER、价格与台数）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CalculationTotals {
+    pub total_btu_95_min: f64,
+    pub total_btu_5_max: f64,
+    pub total_btu_17_max: f64,
+    pub total_btu_17_rated: f64,
+    pub total_btu_design_max: f64,
+
+    pub total_btu_95_rated: f64,
+    pub weighted_hspf_sum: f64,
+    pub weighted_seer_sum: f64,
+
+    // 仅统计有制冷功率数据的机器，按 Btu@95 rated 加权
+    #[serde(default)]
+    pub weighted_eer_sum: f64,
+    #[serde(default)]
+    pub eer_btu_95_rated: f64,
+
+    // 仅统计有价格的机器
+    pub total_price: f64,
+    pub priced_btu_design_max: f64,
+
+    // 目录中找到的机器总台数
+    #[serde(default)]
+    pub total_units: u32,
+}
+
+impl CalculationTotals {
+    /// 按 Btu@95 rated 加权的项目 EER；没有任何功率数据时为 None
+    pub fn project_eer(&self) -> Option<f64> {
+        (self.eer_btu_95_rated > 0.0).then(|| self.weighted_eer_sum / self.eer_btu_95_rated)
+    }
+
+    /// 设计温度下平均每台的最大制热量；没有机器时为 0
+    pub fn btu_per_unit_avg(&self) -> f64 {
+        if self.total_units > 0 { self.total_btu_design_max / self.total_units as f64 } else { 0.0 }
+    }
+}
+
+/// 明细行的合并方式（--aggregate-by）
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AggregateBy {
+    /// Canonical model number
+    Model,
+    /// Machine code (falls back to the model number when a record has no code)
+    Code,
+}
+
+/// 明细行的排序方式（--sort）
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortBy {
+    /// Alphabetical by the aggregation key
+    Model,
+    /// Best capacity per dollar first (lowest $/kBtu at the design temperature)
+    Value,
+}
+
+/// 明细表中的一行（按聚合键合并后的机器）
+#[derive(Debug, Serialize)]
+pub struct RowResult<'a> {
+    pub key: String,
+    pub qty: u32,
+    #[serde(skip)]
+    pub data: &'a MachineData,
+    // 以下容量均为 数量 × 单台
+    pub btu_95_min: f64,
+    pub btu_design_max: f64,
+    // 单台 $/kBtu（目录有价格时）
+    pub price_per_kbtu: Option<f64>,
+    // --derate 系数，已乘进 btu_design_max
+    pub derate: f64,
+    // 单台设计温度制热量的计算过程（降额前），供 --explain 使用
+    #[serde(skip)]
+    pub capacity_detail: Option<Interpolation>,
+}
+
+impl RowResult<'_> {
+    /// 设计温度超出该型号的数据点范围，制热量是外推得到的
+    pub fn is_extrapolated(&self) -> bool {
+        self.capacity_detail.is_some_and(|detail| detail.kind == InterpolationKind::Extrapolated)
+    }
+
+    /// 单台设计温度最大制热量（含降额）占 47°F 最大制热量的比例；没有 Btu@47 max 时为 None
+    pub fn capacity_ratio(&self) -> Option<f64> {
+        let btu_47 = self.data.btu_47_max.filter(|v| *v > 0.0)?;
+        (self.qty > 0).then(|| self.btu_design_max / self.qty as f64 / btu_47)
+    }
+}
+
+/// 目录中找不到的输入标识及其数量
+#[derive(Debug, Serialize)]
+pub struct NotFound {
+    pub identifier: String,
+    pub qty: u32,
+}
+
+/// 一次负荷计算的完整结果：明细行、合计以及找不到的输入
+#[derive(Debug, Serialize)]
+pub struct CalculationResult<'a> {
+    pub rows: Vec<RowResult<'a>>,
+    pub totals: CalculationTotals,
+    pub not_found: Vec<NotFound>,
+}
+
+/// [`perform_calculation`] 的参数；默认值与命令行参数的默认值一致
+///
+/// `CalculationOptions::default().design_temp(5.0).aggregate_by(AggregateBy::Code)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalculationOptions {
+    pub design_temp: f64,
+    pub aggregate_by: AggregateBy,
+    pub sort_by: SortBy,
+    // 始终插值，数据点上也不直接取目录值（--force-interpolation）
+    pub force_interpolation: bool,
+    // 型号 → 容量系数，键已统一成 model number（见 resolve_derates）
+    pub derates: HashMap<String, f64>,
+}
+
+impl Default for CalculationOptions {
+    fn default() -> Self {
+        CalculationOptions {
+            design_temp: 17.0,
+            aggregate_by: AggregateBy::Model,
+            sort_by: SortBy::Model,
+            force_interpolation: false,
+            derates: HashMap::new(),
+        }
+    }
+}
+
+impl CalculationOptions {
+    pub fn design_temp(mut self, design_temp: f64) -> Self {
+        self.design_temp = design_temp;
+        self
+    }
+
+    pub fn aggregate_by(mut self, aggregate_by: AggregateBy) -> Self {
+        self.aggregate_by = aggregate_by;
+        self
+    }
+
+    pub fn sort_by(mut self, sort_by: SortBy) -> Self {
+        self.sort_by = sort_by;
+        self
+    }
+
+    pub fn force_interpolation(mut self, force_interpolation: bool) -> Self {
+        self.force_interpolation = force_interpolation;
+        self
+    }
+
+    pub fn derates(mut self, derates: HashMap<String, f64>) -> Self {
+        self.derates = derates;
+        self
+    }
+}
+
+/// 按型号（或机器代码）合并输入数量，计算每行在设计温度下的制热量以及整体合计；不输出任何内容
+///
+/// 设计温度不是有限的数时返回 [`LcError::InvalidDesignTemp`]；聚合键会合并不同型号时返回
+/// [`LcError::AmbiguousAggregationKey`]
+pub fn perform_calculation<'a>(
+    user_input: &HashMap<String, u32>,
+    machine_data: &'a MachineDatabase,
+    options: &CalculationOptions,
+) -> Result<CalculationResult<'a>, LcError> {
+    let CalculationOptions { design_temp, aggregate_by, sort_by, force_interpolation, ref derates } = *options;
+    if !design_temp.is_finite() {
+        return Err(LcError::InvalidDesignTemp(design_temp));
+    }
+    let mut totals = CalculationTotals::default();
+
+    // 聚合键 → 第一轮解析出的记录和数量；之后不再按聚合键重新查找（代码可能与别的型号同名）
+    let mut canonical_counts: HashMap<String, (&MachineData, u32)> = HashMap::new();
+    let mut not_found: Vec<NotFound> = Vec::new();
+
+    for (identifier, count) in user_input {
+        if let Some(data) = machine_data.get(identifier) {
+            let key = match aggregate_by {
+                AggregateBy::Model => &data.model_number,
+                AggregateBy::Code => data.machine_code.as_ref().unwrap_or(&data.model_number),
+            };
+            let (merged, total) = canonical_counts.entry(key.clone()).or_insert((data, 0));
+            if merged.model_number != data.model_number {
+                return Err(LcError::AmbiguousAggregationKey {
+                    key: key.clone(),
+                    first: merged.model_number.clone(),
+                    second: data.model_number.clone(),
+                });
+            }
+            // 型号和它的机器代码合并到同一个键，数量之和可能超出 u32
+            *total = total.checked_add(*count).ok_or_else(|| LcError::InvalidQuantity {
+                token: identifier.clone(),
+                reason: "total quantity is too large".to_string(),
+            })?;
+        } else {
+            not_found.push(NotFound { identifier: identifier.clone(), qty: *count });
+        }
+    }
+    not_found.sort_by(|a, b| a.identifier.cmp(&b.identifier));
+
+    let mut sorted_models: Vec<_> = canonical_counts.into_iter().collect();
+    sorted_models.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let price_per_kbtu = |data: &MachineData| {
+        let derate = derates.get(&data.model_number).copied().unwrap_or(1.0);
+        data.price_per_kbtu_at_temp(design_temp).map(|v| v / derate)
+    };
+    if sort_by == SortBy::Value {
+        // 没有价格的机器排在最后
+        sorted_models.sort_by(|a, b| match (price_per_kbtu(a.1.0), price_per_kbtu(b.1.0)) {
+            (Some(x), Some(y)) => x.total_cmp(&y),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => a.0.cmp(&b.0),
+        });
+    }
+
+    let mut rows = Vec::new();
+    for (key, (data, count)) in sorted_models {
+        let qty = count as f64;
+        
+        let btu_95_min = data.btu_95_min.unwrap_or(0.0);
+        let derate = derates.get(&data.model_number).copied().unwrap_or(1.0);
+        let capacity_detail = data.heating_capacity_detail(design_temp, force_interpolation);
+        let btu_design_max = capacity_detail.map(|r| r.value * derate).unwrap_or(0.0);
+
+        totals.total_btu_95_min += btu_95_min * qty;
+        totals.total_btu_design_max += btu_design_max * qty;
+        totals.total_units += count;
+        
+        totals.total_btu_5_max += data.btu_5_max.unwrap_or(0.0) * qty;
+        totals.total_btu_17_max += data.btu_17_max.unwrap_or(0.0) * qty;
+        totals.total_btu_17_rated += data.btu_17_rated.unwrap_or(0.0) * qty;
+
+        let btu_95_rated = data.btu_95_rated.unwrap_or(0.0);
+        let hspf = data.hspf.unwrap_or(0.0);
+        let seer = data.seer.unwrap_or(0.0);
+
+        totals.total_btu_95_rated += btu_95_rated * qty;
+        totals.weighted_hspf_sum += btu_95_rated * hspf * qty;
+        if let Some(eer) = data.eer_95_rated() {
+            totals.weighted_eer_sum += btu_95_rated * eer * qty;
+            totals.eer_btu_95_rated += btu_95_rated * qty;
+        }
+        totals.weighted_seer_sum += btu_95_rated * seer * qty;
+
+        let unit_price_per_kbtu = data.price_per_kbtu_at_temp(design_temp).map(|v| v / derate);
+        if let Some(price) = data.price.filter(|_| unit_price_per_kbtu.is_some()) {
+            totals.total_price += price * qty;
+            totals.priced_btu_design_max += btu_design_max * qty;
+        }
+
+        rows.push(RowResult {
+            key,
+            qty: count,
+            data,
+            btu_95_min: btu_95_min * qty,
+            btu_design_max: btu_design_max * qty,
+            price_per_kbtu: unit_price_per_kbtu,
+            derate,
+            capacity_detail,
+        });
+    }
+
+    Ok(CalculationResult { rows, totals, not_found })
+}
+
+// AHRI 210/240 Region IV 各温度区间（°F）占采暖季小时数的比例
+const REGION_IV_BIN_HOURS: &[(f64, f64)] = &[
+    (62.0, 0.132),
+    (57.0, 0.111),
+    (52.0, 0.103),
+    (47.0, 0.093),
+    (42.0, 0.100),
+    (37.0, 0.109),
+    (32.0, 0.126),
+    (27.0, 0.087),
+    (22.0, 0.055),
+    (17.0, 0.036),
+    (12.0, 0.026),
+    (7.0, 0.013),
+    (2.0, 0.006),
+    (-3.0, 0.002),
+    (-8.0, 0.001),
+];
+
+/// 粗略的季节 COP：按区间小时数和 (65 - T) 的建筑负荷加权，季节 COP = Σ负荷 / Σ(负荷 / COP)
+#[derive(Debug, Default)]
+pub struct SeasonalCop {
+    pub per_machine: Vec<(String, f64)>,
+    pub system: Option<f64>,
+}
+
+impl SeasonalCop {
+    /// 按 Region IV 区间小时数加权整个清单；没有 COP 数据时各项为 0
+    pub fn from_result(result: &CalculationResult) -> Self {
+        let mut seasonal = SeasonalCop::default();
+        let (mut system_load, mut system_input) = (0.0, 0.0);
+        for row in &result.rows {
+            let (mut load, mut input) = (0.0, 0.0);
+            for (temp, fraction) in REGION_IV_BIN_HOURS {
+                // 外推出的 COP 不低于电阻加热的 1.0
+                let Some(cop) = row.data.cop_at_temp(*temp).map(|c| c.max(1.0)) else { break };
+                let bin_load = fraction * (65.0 - temp);
+                load += bin_load;
+                input += bin_load / cop;
+            }
+            if input > 0.0 {
+                seasonal.per_machine.push((row.key.clone(), load / input));
+                system_load += load * row.btu_design_max;
+                system_input += input * row.btu_design_max;
+            }
+        }
+        if system_input > 0.0 {
+            seasonal.system = Some(system_load / system_input);
+        }
+        seasonal
+    }
+}
+
+/// 推荐负荷范围：设计温度最大制热量的 1/1.2、1/1.1 和 1 倍
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Recommendation {
+    pub min: f64,
+    pub mid: f64,
+    pub max: f64,
+}
+
+impl Recommendation {
+    /// 由合计计算推荐范围
+    pub fn from_totals(totals: &CalculationTotals) -> Self {
+        let max_val = totals.total_btu_design_max;
+        Recommendation { min: max_val / 1.2, mid: max_val / 1.1, max: max_val }
+    }
+}
+
+/// 设计温度超出目录数据范围（只能外推）或完全没有制热数据的机器
+pub fn missing_data_warnings(result: &CalculationResult, design_temp: f64) -> Vec<String> {
+    result
+        .rows
+        .iter()
+        .filter_map(|row| {
+            let points = row.data.heating_points();
+            let (lowest, highest) = match (points.first(), points.last()) {
+                (Some(first), Some(last)) => (first.0, last.0),
+                _ => return Some(format!("{}: no heating data", row.key)),
+            };
+            if design_temp < lowest {
+                Some(format!("{}: no data below {}°F", row.key, lowest))
+            } else if design_temp > highest {
+                Some(format!("{}: no data above {}°F", row.key, highest))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// --required：设计温度下总制热量是否满足需求
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct RequirementCheck {
+    pub required: f64,
+    pub meets_requirement: bool,
+    // 总制热量占需求的百分比
+    pub oversize_pct: f64,
+}
+
+impl RequirementCheck {
+    /// 比较设计温度下的总最大制热量与需求负荷
+    pub fn new(totals: &CalculationTotals, required: f64) -> Self {
+        let capacity = totals.total_btu_design_max;
+        RequirementCheck {
+            required,
+            meets_requirement: capacity >= required,
+            oversize_pct: if required > 0.0 { capacity / required * 100.0 } else { 0.0 },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{CatalogFormat, parse_machine_data};
+    use crate::input::canonical_scenario;
+    use proptest::prelude::*;
+
+    // AAA18 有完整的制热数据点和价格；BBB24 没有最低温度点，也没有价格
+    const FIXTURE: &str = "\
+model number,machine code,AHRI,Btu@95min,Btu@lowest max,lowest temperature,Btu@5max,Btu@17max,Btu@17rated,Btu@47max,Btu@95rated,HSPF,SEER,Price
+AAA18,18A,1001,6000,8000,-13,12000,15000,14000,18000,18000,10,20,900
+BBB24,24B,1002,8000,,,18000,21000,20000,24000,24000,9,18,
+";
+
+    fn fixture() -> MachineDatabase {
+        parse_machine_data(FIXTURE, "fixture", CatalogFormat::default(), false, false).unwrap()
+    }
+
+    fn input(items: &[(&str, u32)]) -> HashMap<String, u32> {
+        items.iter().map(|(id, qty)| (id.to_string(), *qty)).collect()
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-6, "expected {expected}, got {actual}");
+    }
+
+    #[test]
+    fn rows_and_totals_at_a_catalog_point() {
+        let catalog = fixture();
+        let user_input = input(&[("AAA18", 2), ("24B", 1), ("UNKNOWN", 3)]);
+        let result = perform_calculation(&user_input, &catalog, &CalculationOptions::default()).unwrap();
+
+        let keys: Vec<&str> = result.rows.iter().map(|row| row.key.as_str()).collect();
+        assert_eq!(keys, ["AAA18", "BBB24"]);
+        let (aaa, bbb) = (&result.rows[0], &result.rows[1]);
+        assert_eq!((aaa.qty, bbb.qty), (2, 1));
+        assert_close(aaa.btu_95_min, 12000.0);
+        assert_close(aaa.btu_design_max, 30000.0);
+        assert_eq!(aaa.price_per_kbtu, Some(60.0));
+        assert_eq!(aaa.capacity_detail.unwrap().kind, InterpolationKind::Exact);
+        assert_close(bbb.btu_design_max, 21000.0);
+        assert_eq!(bbb.price_per_kbtu, None);
+
+        let totals = &result.totals;
+        assert_close(totals.total_btu_95_min, 20000.0);
+        assert_close(totals.total_btu_5_max, 42000.0);
+        assert_close(totals.total_btu_17_max, 51000.0);
+        assert_close(totals.total_btu_17_rated, 48000.0);
+        assert_close(totals.total_btu_design_max, 51000.0);
+        assert_close(totals.total_btu_95_rated, 60000.0);
+        assert_close(totals.weighted_hspf_sum, 2.0 * 18000.0 * 10.0 + 24000.0 * 9.0);
+        assert_close(totals.weighted_seer_sum, 2.0 * 18000.0 * 20.0 + 24000.0 * 18.0);
+        assert_close(totals.total_price, 1800.0);
+        assert_close(totals.priced_btu_design_max, 30000.0);
+        assert_eq!(totals.total_units, 3);
+
+        assert_eq!(result.not_found.len(), 1);
+        assert_eq!((result.not_found[0].identifier.as_str(), result.not_found[0].qty), ("UNKNOWN", 3));
+    }
+
+    #[test]
+    fn design_capacity_is_interpolated_and_derated() {
+        let catalog = fixture();
+        let derates = HashMap::from([("AAA18".to_string(), 0.9)]);
+        let options = CalculationOptions::default().design_temp(11.0).derates(derates);
+        let result = perform_calculation(&input(&[("AAA18", 1)]), &catalog, &options).unwrap();
+
+        let row = &result.rows[0];
+        let detail = row.capacity_detail.unwrap();
+        assert_eq!(detail.kind, InterpolationKind::Interpolated);
+        assert_eq!(detail.bracket, [(5.0, 12000.0), (17.0, 15000.0)]);
+        assert_close(detail.value, 13500.0);
+        assert_close(row.btu_design_max, 13500.0 * 0.9);
+        assert_close(result.totals.total_btu_design_max, 13500.0 * 0.9);
+        assert!(!row.is_extrapolated());
+    }
+
+    #[test]
+    fn below_the_lowest_point_is_extrapolated() {
+        let catalog = fixture();
+        let result =
+            perform_calculation(&input(&[("AAA18", 1)]), &catalog, &CalculationOptions::default().design_temp(-20.0)).unwrap();
+        assert!(result.rows[0].is_extrapolated());
+        assert_close(result.rows[0].btu_design_max, 8000.0 - 7.0 * 4000.0 / 18.0);
+    }
+
+    #[test]
+    fn model_and_code_inputs_merge_into_one_row() {
+        let catalog = fixture();
+        let user_input = input(&[("AAA18", 1), ("18A", 2)]);
+        for (aggregate_by, key) in [(AggregateBy::Model, "AAA18"), (AggregateBy::Code, "18A")] {
+            let result = perform_calculation(&user_input, &catalog, &CalculationOptions::default().aggregate_by(aggregate_by)).unwrap();
+            assert_eq!(result.rows.len(), 1);
+            assert_eq!((result.rows[0].key.as_str(), result.rows[0].qty), (key, 3));
+            assert_close(result.totals.total_btu_design_max, 45000.0);
+        }
+    }
+
+    #[test]
+    fn aggregating_by_code_never_swaps_catalog_records() {
+        // BBB24 与 AAA18 共用代码 18A；CCC30 的代码恰好是 AAA18 的 model number
+        let catalog = parse_machine_data(
+            "\
+model number,machine code,AHRI,Btu@95min,Btu@lowest max,lowest temperature,Btu@5max,Btu@17max,Btu@17rated,Btu@47max,Btu@95rated,HSPF,SEER,Price
+AAA18,18A,1001,6000,,,,15000,,,,,,
+BBB24,18A,1002,8000,,,,30000,,,,,,
+CCC30,AAA18,1003,9000,,,,50000,,,,,,
+",
+            "fixture",
+            CatalogFormat::default(),
+            false,
+            false,
+        )
+        .unwrap();
+        let by_code = CalculationOptions::default().aggregate_by(AggregateBy::Code);
+
+        let err = perform_calculation(&input(&[("AAA18", 1), ("BBB24", 1)]), &catalog, &by_code).unwrap_err();
+        assert!(matches!(err, LcError::AmbiguousAggregationKey { ref key, .. } if key == "18A"), "{err:?}");
+
+        let result = perform_calculation(&input(&[("CCC30", 1), ("AAA18", 1)]), &catalog, &by_code).unwrap();
+        let rows: Vec<(&str, &str, f64)> =
+            result.rows.iter().map(|row| (row.key.as_str(), row.data.model_number.as_str(), row.btu_design_max)).collect();
+        assert_eq!(rows, vec![("18A", "AAA18", 15000.0), ("AAA18", "CCC30", 50000.0)]);
+    }
+
+    #[test]
+    fn non_finite_design_temp_is_rejected() {
+        let catalog = fixture();
+        let options = CalculationOptions::default().design_temp(f64::NAN);
+        let result = perform_calculation(&input(&[("AAA18", 1)]), &catalog, &options);
+        assert!(matches!(result, Err(LcError::InvalidDesignTemp(t)) if t.is_nan()));
+    }
+
+    #[test]
+    fn interpolate_points_covers_every_branch() {
+        assert!(interpolate_points(&[], 17.0).is_none());
+
+        let single = interpolate_points(&[(47.0, 20000.0)], 5.0).unwrap();
+        assert_eq!((single.value, single.kind), (20000.0, InterpolationKind::SinglePoint));
+
+        let points = [(5.0, 12000.0), (17.0, 15000.0), (47.0, 21000.0)];
+        let cases = [
+            (-1.0, 10500.0, InterpolationKind::Extrapolated),
+            (5.0, 12000.0, InterpolationKind::Interpolated),
+            (11.0, 13500.0, InterpolationKind::Interpolated),
+            (32.0, 18000.0, InterpolationKind::Interpolated),
+            (47.0, 21000.0, InterpolationKind::Interpolated),
+            (57.0, 23000.0, InterpolationKind::Extrapolated),
+        ];
+        for (temp, value, kind) in cases {
+            let result = interpolate_points(&points, temp).unwrap();
+            assert_eq!((result.value, result.kind), (value, kind), "at {temp}°F");
+        }
+
+        // 同一温度的两个点：不做除法，取第一个点的值
+        let duplicate = interpolate_points(&[(5.0, 11000.0), (5.0, 12000.0)], 0.0).unwrap();
+        assert_eq!(duplicate.value, 11000.0);
+
+        // NaN 不落在任何区间内，沿用第一段，结果仍为 NaN
+        assert!(interpolate_points(&points, f64::NAN).unwrap().value.is_nan());
+    }
+
+    // 2~6 个温度互不相同的升序数据点
+    fn sorted_points() -> impl Strategy<Value = Vec<(f64, f64)>> {
+        prop::collection::btree_map(-60i32..120, 0.0f64..100_000.0, 2..7)
+            .prop_map(|points| points.into_iter().map(|(temp, btu)| (f64::from(temp), btu)).collect())
+    }
+
+    fn value_at(points: &[(f64, f64)], temp: f64) -> f64 {
+        interpolate_points(points, temp).unwrap().value
+    }
+
+    proptest! {
+        #[test]
+        fn knots_return_their_own_value(points in sorted_points()) {
+            for &(temp, btu) in &points {
+                prop_assert!((value_at(&points, temp) - btu).abs() < 1e-6, "at {}°F", temp);
+            }
+        }
+
+        #[test]
+        fn values_between_knots_lie_between_the_brackets(points in sorted_points(), fraction in 0.0f64..1.0) {
+            for pair in points.windows(2) {
+                let [(t1, y1), (t2, y2)] = [pair[0], pair[1]];
+                let value = value_at(&points, t1 + (t2 - t1) * fraction);
+                prop_assert!(value >= y1.min(y2) - 1e-6 && value <= y1.max(y2) + 1e-6, "{} not within [{}, {}]", value, y1, y2);
+            }
+        }
+
+        #[test]
+        fn interpolation_is_continuous_at_the_knots(points in sorted_points()) {
+            const EPSILON: f64 = 1e-6;
+            // 相邻两段斜率的绝对值不超过 100000 Btu/°F
+            for &(temp, btu) in &points {
+                for side in [temp - EPSILON, temp + EPSILON] {
+                    prop_assert!((value_at(&points, side) - btu).abs() < 0.2, "jump next to {}°F", temp);
+                }
+            }
+        }
+
+        #[test]
+        fn model_and_code_quantities_merge_without_overflowing(model_qty in any::<u32>(), code_qty in any::<u32>()) {
+            let catalog = fixture();
+            let user_input = input(&[("AAA18", model_qty), ("18A", code_qty)]);
+            let total = u32::try_from(u64::from(model_qty) + u64::from(code_qty)).ok();
+            match perform_calculation(&user_input, &catalog, &CalculationOptions::default()) {
+                Ok(result) => prop_assert_eq!(Some(result.rows[0].qty), total),
+                Err(e) => prop_assert!(total.is_none() && matches!(e, LcError::InvalidQuantity { .. }), "{:?}", e),
+            }
+            match canonical_scenario(&user_input, &catalog) {
+                Ok(scenario) => prop_assert_eq!(scenario, vec![("AAA18".to_string(), total.unwrap())]),
+                Err(e) => prop_assert!(total.is_none() && matches!(e, LcError::InvalidQuantity { .. }), "{:?}", e),
+            }
+        }
+
+        #[test]
+        fn arbitrary_catalog_values_do_not_panic(
+            lowest_temp in prop::option::of(any::<f64>()),
+            values in prop::array::uniform5(prop::option::of(any::<f64>())),
+            target in any::<f64>(),
+            force_interpolation in any::<bool>(),
+        ) {
+            let machine = MachineData {
+                lowest_temp,
+                btu_lowest_max: values[0],
+                btu_5_max: values[1],
+                btu_17_max: values[2],
+                btu_47_max: values[3],
+                cop_lowest_max: values[4],
+                ..Default::default()
+            };
+            let _ = machine.heating_capacity_detail(target, force_interpolation);
+            let _ = machine.cop_at_temp(target);
+        }
+    }
+}
@@ -0,0 +1,1071 @@
+//! 目录浏览类子命令：search、info、interp、top、stats、missing、codes、suggest、compare 等
+
+use std::collections::BTreeMap;
+
+use clap::ValueEnum;
+use comfy_table::{Table, Attribute, Cell, CellAlignment, Color};
+use regex::RegexBuilder;
+use serde::Serialize;
+
+use crate::data::{MachineData, MachineDatabase, unknown_identifier_error};
+use crate::render::{
+    OutputFormat, TableOptions, create_format_table, create_styled_table, format_optional_btu,
+    print_catalog_rows, print_section_title,
+};
+
+/// `lc missing --only` 可检查的目录字段
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataField {
+    Ahri,
+    #[value(name = "btu_95_min")]
+    Btu95Min,
+    #[value(name = "btu_5_max")]
+    Btu5Max,
+    #[value(name = "btu_17_max")]
+    Btu17Max,
+    #[value(name = "btu_17_rated")]
+    Btu17Rated,
+    #[value(name = "btu_47_max")]
+    Btu47Max,
+    /// lowest_temp together with btu_lowest_max
+    Lowest,
+}
+
+impl DataField {
+    const ALL: [DataField; 7] = [
+        DataField::Ahri,
+        DataField::Btu95Min,
+        DataField::Btu5Max,
+        DataField::Btu17Max,
+        DataField::Btu17Rated,
+        DataField::Btu47Max,
+        DataField::Lowest,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            DataField::Ahri => "ahri",
+            DataField::Btu95Min => "btu_95_min",
+            DataField::Btu5Max => "btu_5_max",
+            DataField::Btu17Max => "btu_17_max",
+            DataField::Btu17Rated => "btu_17_rated",
+            DataField::Btu47Max => "btu_47_max",
+            DataField::Lowest => "lowest",
+        }
+    }
+
+    fn is_present(self, data: &MachineData) -> bool {
+        match self {
+            DataField::Ahri => data.ahri.is_some(),
+            DataField::Btu95Min => data.btu_95_min.is_some(),
+            DataField::Btu5Max => data.btu_5_max.is_some(),
+            DataField::Btu17Max => data.btu_17_max.is_some(),
+            DataField::Btu17Rated => data.btu_17_rated.is_some(),
+            DataField::Btu47Max => data.btu_47_max.is_some(),
+            DataField::Lowest => data.lowest_temp.is_some() && data.btu_lowest_max.is_some(),
+        }
+    }
+}
+
+/// `lc top --by` 的排序指标
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TopMetric {
+    #[value(name = "btu-5-max")]
+    Btu5Max,
+    #[value(name = "btu-17-max")]
+    Btu17Max,
+    #[value(name = "btu-47-max")]
+    Btu47Max,
+    /// Max capacity at --design-temp (interpolated)
+    Design,
+}
+
+/// `lc curve-compare` 的输出格式
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CurveFormat {
+    Chart,
+    Csv,
+}
+
+/// `lc stats` 的输出格式
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StatsFormat {
+    Table,
+    Markdown,
+    Json,
+}
+
+/// `lc search`：在型号、机器代码和 AHRI 编号中查找（子串或正则），匹配部分高亮
+///
+/// `min_temp_rating`：只保留 [`MachineData::lowest_operating_temp`] 不高于该温度的型号
+pub fn search_catalog(
+    machine_data: &MachineDatabase,
+    pattern: Option<&str>,
+    use_regex: bool,
+    min_temp_rating: Option<f64>,
+    limit: usize,
+    format: OutputFormat,
+    table_options: TableOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let re = pattern
+        .map(|pattern| {
+            let source = if use_regex { pattern.to_string() } else { regex::escape(pattern) };
+            RegexBuilder::new(&source)
+                .case_insensitive(true)
+                .build()
+                .map_err(|e| format!("Invalid search pattern: {}", e))
+        })
+        .transpose()?;
+
+    let matches: Vec<&MachineData> = machine_data
+        .models()
+        .filter(|data| {
+            re.as_ref().is_none_or(|re| {
+                re.is_match(&data.model_number)
+                    || data.machine_code.as_deref().is_some_and(|code| re.is_match(code))
+                    || data.ahri.is_some_and(|ahri| re.is_match(&ahri.to_string()))
+            })
+        })
+        .filter(|data| min_temp_rating.is_none_or(|rating| data.lowest_operating_temp().is_some_and(|t| t <= rating)))
+        .collect();
+
+    if matches.is_empty() {
+        let mut message = "No models".to_string();
+        if let Some(pattern) = pattern {
+            message.push_str(&format!(" match \"{}\"", pattern));
+        }
+        if let Some(rating) = min_temp_rating {
+            message.push_str(&format!(" rated to {}°F or colder", rating));
+        }
+        return Err(message.into());
+    }
+
+    let shown = &matches[..matches.len().min(limit)];
+    print_catalog_rows(shown, format, re.as_ref(), table_options)?;
+    if shown.len() < matches.len() {
+        eprintln!(" Showing {} of {} matches (use --limit to see more)", shown.len(), matches.len());
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+struct TopRow<'a> {
+    rank: usize,
+    model_number: &'a str,
+    machine_code: Option<&'a str>,
+    btu_95_min: Option<f64>,
+    metric: f64,
+}
+
+/// `lc top`：按容量指标从大到小排列目录型号
+pub fn top_models(
+    machine_data: &MachineDatabase,
+    by: TopMetric,
+    max_min_capacity: Option<f64>,
+    limit: usize,
+    design_temp: f64,
+    format: OutputFormat,
+    table_options: TableOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let metric = |data: &MachineData| match by {
+        TopMetric::Btu5Max => data.btu_5_max,
+        TopMetric::Btu17Max => data.btu_17_max,
+        TopMetric::Btu47Max => data.btu_47_max,
+        TopMetric::Design => Some(data.calculate_heating_capacity_at_temp(design_temp))
+            .filter(|_| !data.heating_points().is_empty()),
+    };
+    let metric_label = match by {
+        TopMetric::Btu5Max => "Btu@5 max".to_string(),
+        TopMetric::Btu17Max => "Btu@17 max".to_string(),
+        TopMetric::Btu47Max => "Btu@47 max".to_string(),
+        TopMetric::Design => format!("Btu@{} max", design_temp),
+    };
+
+    let candidates: Vec<&MachineData> = machine_data
+        .models()
+        .filter(|data| max_min_capacity.is_none_or(|max| data.btu_95_min.is_some_and(|min| min <= max)))
+        .collect();
+    let mut ranked: Vec<(&MachineData, f64)> =
+        candidates.iter().filter_map(|data| metric(data).map(|value| (*data, value))).collect();
+    let missing = candidates.len() - ranked.len();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.model_number.cmp(&b.0.model_number)));
+
+    let rows: Vec<TopRow> = ranked
+        .into_iter()
+        .take(limit)
+        .enumerate()
+        .map(|(i, (data, value))| TopRow {
+            rank: i + 1,
+            model_number: &data.model_number,
+            machine_code: data.machine_code.as_deref(),
+            btu_95_min: data.btu_95_min,
+            metric: value,
+        })
+        .collect();
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(std::io::stdout());
+            for row in &rows {
+                writer.serialize(row)?;
+            }
+            writer.flush()?;
+        }
+        OutputFormat::Table | OutputFormat::Markdown => {
+            let mut table = create_format_table(format, table_options);
+            table.set_header(vec![
+                Cell::new("#"),
+                Cell::new("Model"),
+                Cell::new("Code"),
+                Cell::new("Btu@95 min"),
+                Cell::new(&metric_label).add_attribute(Attribute::Bold),
+            ]);
+            for row in &rows {
+                table.add_row(vec![
+                    Cell::new(row.rank),
+                    Cell::new(row.model_number),
+                    Cell::new(row.machine_code.unwrap_or("-")),
+                    Cell::new(format_optional_btu(row.btu_95_min)).set_alignment(CellAlignment::Right),
+                    Cell::new(format!("{:.0}", row.metric))
+                        .set_alignment(CellAlignment::Right)
+                        .add_attribute(Attribute::Bold)
+                        .fg(Color::Green),
+                ]);
+            }
+            println!("{table}");
+        }
+    }
+    if missing > 0 {
+        eprintln!(" {} models without {} data were excluded", missing, metric_label);
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+struct SpreadStats {
+    count: usize,
+    min: f64,
+    median: f64,
+    max: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct HistogramBucket {
+    from: f64,
+    to: f64,
+    count: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct CatalogStats {
+    models: usize,
+    by_series: BTreeMap<String, usize>,
+    btu_47_max: Option<SpreadStats>,
+    // 有 5°F 及以下制热数据的型号数
+    low_temp_models: usize,
+    ahri_models: usize,
+    bucket_size: f64,
+    histogram: Vec<HistogramBucket>,
+}
+
+// 直方图最多的桶数；--bucket 太小时报错，而不是分配数以亿计的空桶
+const MAX_HISTOGRAM_BUCKETS: i64 = 200;
+
+fn catalog_stats(
+    machine_data: &MachineDatabase,
+    prefix_len: usize,
+    bucket_size: f64,
+) -> Result<CatalogStats, Box<dyn std::error::Error>> {
+    let models: Vec<&MachineData> = machine_data.models().collect();
+    let mut by_series: BTreeMap<String, usize> = BTreeMap::new();
+    for data in &models {
+        *by_series.entry(data.model_number.chars().take(prefix_len).collect()).or_insert(0) += 1;
+    }
+
+    let mut values: Vec<f64> = models.iter().filter_map(|data| data.btu_47_max).collect();
+    values.sort_by(f64::total_cmp);
+    let btu_47_max = (!values.is_empty()).then(|| {
+        let mid = values.len() / 2;
+        let median = if values.len().is_multiple_of(2) { (values[mid - 1] + values[mid]) / 2.0 } else { values[mid] };
+        SpreadStats { count: values.len(), min: values[0], median, max: values[values.len() - 1] }
+    });
+
+    // 从最小值所在的桶到最大值所在的桶，中间的空桶也保留
+    let mut histogram = Vec::new();
+    if let (Some(first), Some(last)) = (values.first(), values.last()) {
+        let first_bucket = (first / bucket_size).floor() as i64;
+        let last_bucket = (last / bucket_size).floor() as i64;
+        if last_bucket.saturating_sub(first_bucket) >= MAX_HISTOGRAM_BUCKETS {
+            return Err(format!(
+                "--bucket {} would split Btu@47 max {:.0} - {:.0} into more than {} buckets; use a larger bucket size",
+                bucket_size, first, last, MAX_HISTOGRAM_BUCKETS
+            )
+            .into());
+        }
+        for bucket in first_bucket..=last_bucket {
+            let from = bucket as f64 * bucket_size;
+            let to = from + bucket_size;
+            let count = values.iter().filter(|v| **v >= from && **v < to).count();
+            histogram.push(HistogramBucket { from, to, count });
+        }
+    }
+
+    Ok(CatalogStats {
+        models: models.len(),
+        by_series,
+        btu_47_max,
+        low_temp_models: models
+            .iter()
+            .filter(|data| data.heating_points().iter().any(|(temp, _)| *temp <= 5.0))
+            .count(),
+        ahri_models: models.iter().filter(|data| data.ahri.is_some()).count(),
+        bucket_size,
+        histogram,
+    })
+}
+
+/// `lc stats`：按型号前缀统计数量、Btu@47 max 的分布以及容量直方图
+pub fn print_catalog_stats(
+    machine_data: &MachineDatabase,
+    prefix_len: usize,
+    bucket_size: f64,
+    format: StatsFormat,
+    table_options: TableOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if bucket_size.is_nan() || bucket_size <= 0.0 {
+        return Err("--bucket must be greater than 0".into());
+    }
+    let stats = catalog_stats(machine_data, prefix_len, bucket_size)?;
+    if format == StatsFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(());
+    }
+    let table_format = if format == StatsFormat::Markdown { OutputFormat::Markdown } else { OutputFormat::Table };
+    let right = |text: String| Cell::new(text).set_alignment(CellAlignment::Right);
+
+    let mut overview = create_format_table(table_format, table_options);
+    overview.set_header(vec!["Metric", "Value"]);
+    overview.add_row(vec![Cell::new("Models"), right(stats.models.to_string())]);
+    overview.add_row(vec![Cell::new("With AHRI number"), right(stats.ahri_models.to_string())]);
+    overview.add_row(vec![Cell::new("With data at or below 5°F"), right(stats.low_temp_models.to_string())]);
+    if let Some(spread) = &stats.btu_47_max {
+        overview.add_row(vec![Cell::new("Btu@47 max (models)"), right(spread.count.to_string())]);
+        overview.add_row(vec![Cell::new("Btu@47 max min"), right(format!("{:.0}", spread.min))]);
+        overview.add_row(vec![Cell::new("Btu@47 max median"), right(format!("{:.0}", spread.median))]);
+        overview.add_row(vec![Cell::new("Btu@47 max max"), right(format!("{:.0}", spread.max))]);
+    }
+    println!("{overview}");
+
+    let mut series = create_format_table(table_format, table_options);
+    series.set_header(vec!["Series", "Models"]);
+    for (prefix, count) in &stats.by_series {
+        series.add_row(vec![Cell::new(format!("{}*", prefix)), right(count.to_string())]);
+    }
+    println!("\n{series}");
+
+    // 柱长不超过 50 个 #，桶内型号多时按比例缩短
+    let largest = stats.histogram.iter().map(|b| b.count).max().unwrap_or(0);
+    let scale = if largest > 50 { 50.0 / largest as f64 } else { 1.0 };
+    let mut histogram = create_format_table(table_format, table_options);
+    histogram.set_header(vec!["Btu@47 max", "Models", ""]);
+    for bucket in &stats.histogram {
+        let bar = "#".repeat((bucket.count as f64 * scale).round() as usize);
+        histogram.add_row(vec![
+            Cell::new(format!("{:.0} - {:.0}", bucket.from, bucket.to)),
+            right(bucket.count.to_string()),
+            Cell::new(bar),
+        ]);
+    }
+    println!("\n{histogram}");
+    Ok(())
+}
+
+/// 缺失字段矩阵：每行一个至少缺一个字段的型号，末尾统计每个字段缺失的数量
+pub fn print_missing_fields(
+    machine_data: &MachineDatabase,
+    only: Option<DataField>,
+    format: OutputFormat,
+    table_options: TableOptions,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let models: Vec<&MachineData> = machine_data
+        .models()
+        .filter(|data| match only {
+            Some(field) => !field.is_present(data),
+            None => DataField::ALL.iter().any(|field| !field.is_present(data)),
+        })
+        .collect();
+    let counts: Vec<usize> = DataField::ALL
+        .iter()
+        .map(|field| models.iter().filter(|data| !field.is_present(data)).count())
+        .collect();
+
+    match format {
+        OutputFormat::Json => {
+            let rows: Vec<serde_json::Value> = models
+                .iter()
+                .map(|data| {
+                    let missing: Vec<&str> =
+                        DataField::ALL.iter().filter(|f| !f.is_present(data)).map(|f| f.name()).collect();
+                    serde_json::json!({ "model_number": data.model_number, "missing": missing })
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&rows)?);
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(std::io::stdout());
+            let mut header = vec!["model_number"];
+            header.extend(DataField::ALL.iter().map(|f| f.name()));
+            writer.write_record(&header)?;
+            for data in &models {
+                let mut record = vec![data.model_number.as_str()];
+                record.extend(DataField::ALL.iter().map(|f| if f.is_present(data) { "1" } else { "0" }));
+                writer.write_record(&record)?;
+            }
+            writer.flush()?;
+        }
+        OutputFormat::Table | OutputFormat::Markdown => {
+            let mut table = create_format_table(format, table_options);
+            let mut header = vec![Cell::new("Model")];
+            header.extend(DataField::ALL.iter().map(|f| Cell::new(f.name())));
+            table.set_header(header);
+            for data in &models {
+                let mut cells = vec![Cell::new(&data.model_number)];
+                cells.extend(DataField::ALL.iter().map(|f| {
+                    let cell = if f.is_present(data) {
+                        Cell::new("✓").fg(Color::Green)
+                    } else {
+                        Cell::new("–").fg(Color::Red)
+                    };
+                    cell.set_alignment(CellAlignment::Center)
+                }));
+                table.add_row(cells);
+            }
+            let mut footer = vec![Cell::new("Missing").add_attribute(Attribute::Bold)];
+            footer.extend(counts.iter().map(|c| Cell::new(c).set_alignment(CellAlignment::Center)));
+            table.add_row(footer);
+            println!("{table}");
+        }
+    }
+    Ok(!models.is_empty())
+}
+
+/// `lc ahri`：列出使用该 AHRI 编号认证的型号
+pub fn lookup_ahri(
+    machine_data: &MachineDatabase,
+    number: u64,
+    format: OutputFormat,
+    table_options: TableOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let matches = machine_data.by_ahri(number);
+    if matches.is_empty() {
+        return Err(format!("No model found for AHRI {}", number).into());
+    }
+    print_catalog_rows(&matches, format, None, table_options)
+}
+
+/// `lc info`：单个型号的全部字段以及设计温度下的派生值
+pub fn print_machine_info(
+    machine_data: &MachineDatabase,
+    identifier: &str,
+    design_temp: f64,
+    format: OutputFormat,
+    table_options: TableOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let data = machine_data.get(identifier)
+        .ok_or_else(|| unknown_identifier_error(machine_data, identifier))?;
+
+    let fields = data.numeric_fields();
+    let points = data.heating_points();
+    let retention = data.retention_at_temp(17.0);
+    let capacity = data.calculate_heating_capacity_at_temp(design_temp);
+
+    match format {
+        OutputFormat::Json => {
+            let mut record = serde_json::Map::new();
+            record.insert("model_number".into(), data.model_number.clone().into());
+            record.insert("machine_code".into(), data.machine_code.clone().into());
+            record.insert("ahri".into(), data.ahri.into());
+            for (name, value) in &fields {
+                record.insert(name.to_string(), (*value).into());
+            }
+            let missing: Vec<&str> = fields.iter().filter(|(_, v)| v.is_none()).map(|(name, _)| *name).collect();
+            let report = serde_json::json!({
+                "record": record,
+                "missing": missing,
+                "heating_points": points,
+                "retention_17": retention,
+                "design_temp": design_temp,
+                "btu_design_max": capacity,
+            });
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(std::io::stdout());
+            writer.write_record(["field", "value"])?;
+            writer.write_record(["model_number", data.model_number.as_str()])?;
+            writer.write_record(["machine_code", data.machine_code.as_deref().unwrap_or("")])?;
+            writer.write_record(["ahri", &data.ahri.map(|v| v.to_string()).unwrap_or_default()])?;
+            for (name, value) in &fields {
+                writer.write_record([*name, &value.map(|v| v.to_string()).unwrap_or_default()])?;
+            }
+            writer.write_record(["retention_17", &retention.map(|v| v.to_string()).unwrap_or_default()])?;
+            writer.write_record(["btu_design_max", &capacity.to_string()])?;
+            writer.flush()?;
+        }
+        OutputFormat::Table | OutputFormat::Markdown => {
+            if format == OutputFormat::Markdown {
+                println!("### {}\n", data.model_number);
+            } else {
+                print_section_title(&data.model_number, Color::Blue, table_options);
+            }
+
+            let mut table = create_format_table(format, table_options);
+            let missing_cell = || Cell::new("missing").add_attribute(Attribute::Dim).fg(Color::Red);
+            let ahri_cell = data.ahri.map(Cell::new).unwrap_or_else(missing_cell);
+            let code_cell = data.machine_code.as_ref().map(Cell::new).unwrap_or_else(missing_cell);
+            table.add_row(vec![Cell::new("machine_code"), code_cell]);
+            table.add_row(vec![Cell::new("ahri"), ahri_cell]);
+            for (name, value) in &fields {
+                let value_cell = match value {
+                    Some(v) => Cell::new(v).set_alignment(CellAlignment::Right),
+                    None => missing_cell(),
+                };
+                table.add_row(vec![Cell::new(name), value_cell]);
+            }
+            println!("{table}");
+
+            println!();
+            let mut derived = create_format_table(format, table_options);
+            let point_list: Vec<String> = points.iter().map(|(t, btu)| format!("{}°F: {:.0}", t, btu)).collect();
+            derived.add_row(vec![Cell::new("Heating points"), Cell::new(point_list.join(", "))]);
+            derived.add_row(vec![
+                Cell::new("Retention @17"),
+                Cell::new(retention.map(|r| format!("{:.1}%", r * 100.0)).unwrap_or("-".to_string())),
+            ]);
+            derived.add_row(vec![
+                Cell::new(format!("Btu@{} max", design_temp)).fg(Color::Green),
+                Cell::new(format!("{:.0}", capacity)).add_attribute(Attribute::Bold),
+            ]);
+            println!("{derived}");
+        }
+    }
+    Ok(())
+}
+
+const CURVE_GLYPHS: [char; 5] = ['*', 'o', '+', 'x', '#'];
+const CURVE_CHART_HEIGHT: usize = 16;
+// CSV 输出最多的行数；--step 太小时报错，而不是输出上亿行
+const MAX_CURVE_ROWS: usize = 1000;
+
+/// `lc curve-compare`：横轴温度、纵轴 Btu 的 ASCII 折线图，纵轴上限取各曲线中的最大值
+pub fn curve_compare(
+    machine_data: &MachineDatabase,
+    identifiers: &[String],
+    from: f64,
+    to: f64,
+    step: f64,
+    force_interpolation: bool,
+    format: CurveFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if from >= to {
+        return Err("--from must be lower than --to".into());
+    }
+    let mut models: Vec<&MachineData> = Vec::new();
+    for identifier in identifiers {
+        let data = machine_data.get(identifier)
+            .ok_or_else(|| unknown_identifier_error(machine_data, identifier))?;
+        if data.heating_points().is_empty() {
+            return Err(format!("{} has no heating data points", data.model_number).into());
+        }
+        models.push(data);
+    }
+    let capacity = |data: &MachineData, temp: f64| {
+        data.heating_capacity_detail(temp, force_interpolation).map(|r| r.value).unwrap_or(0.0)
+    };
+
+    if format == CurveFormat::Csv {
+        if !step.is_finite() || step <= 0.0 {
+            return Err("--step must be greater than 0".into());
+        }
+        let steps = ((to - from) / step).floor();
+        if steps >= MAX_CURVE_ROWS as f64 {
+            return Err(format!(
+                "--step {} would print more than {} rows from {}°F to {}°F; use a larger step",
+                step, MAX_CURVE_ROWS, from, to
+            )
+            .into());
+        }
+        let steps = steps as usize;
+        let mut writer = csv::Writer::from_writer(std::io::stdout());
+        let mut header = vec!["temp".to_string()];
+        header.extend(models.iter().map(|data| data.model_number.clone()));
+        writer.write_record(&header)?;
+        for i in 0..=steps {
+            let temp = from + step * i as f64;
+            let mut record = vec![temp.to_string()];
+            record.extend(models.iter().map(|data| format!("{:.0}", capacity(data, temp))));
+            writer.write_record(&record)?;
+        }
+        writer.flush()?;
+        return Ok(());
+    }
+
+    // 终端宽度减去纵轴标签；非终端输出时按 80 列
+    let terminal_width = Table::new().width().map(usize::from).unwrap_or(80);
+    let label_width = 8;
+    let plot_width = terminal_width.saturating_sub(label_width + 2).clamp(20, 160);
+    let temp_at = |column: usize| from + (to - from) * column as f64 / (plot_width - 1) as f64;
+    let curves: Vec<Vec<f64>> =
+        models.iter().map(|data| (0..plot_width).map(|column| capacity(data, temp_at(column))).collect()).collect();
+    let y_max = curves.iter().flatten().copied().fold(0.0, f64::max);
+    if y_max <= 0.0 {
+        return Err("no positive capacity in the temperature range".into());
+    }
+
+    let mut grid = vec![vec![' '; plot_width]; CURVE_CHART_HEIGHT];
+    for (curve, glyph) in curves.iter().zip(CURVE_GLYPHS) {
+        for (column, value) in curve.iter().enumerate() {
+            let level = (value.max(0.0) / y_max * (CURVE_CHART_HEIGHT - 1) as f64).round() as usize;
+            grid[CURVE_CHART_HEIGHT - 1 - level][column] = glyph;
+        }
+    }
+    for (row, line) in grid.iter().enumerate() {
+        // 纵轴只在顶部、中间和底部标数值
+        let level = CURVE_CHART_HEIGHT - 1 - row;
+        let label = if level == CURVE_CHART_HEIGHT - 1 || level == 0 || level == (CURVE_CHART_HEIGHT - 1) / 2 {
+            format!("{:.0}", y_max * level as f64 / (CURVE_CHART_HEIGHT - 1) as f64)
+        } else {
+            String::new()
+        };
+        println!("{:>width$} |{}", label, line.iter().collect::<String>(), width = label_width);
+    }
+    println!("{:>width$} +{}", "", "-".repeat(plot_width), width = label_width);
+    let (left, middle, right) = (format!("{}°F", from), format!("{}°F", (from + to) / 2.0), format!("{}°F", to));
+    let middle_start = (plot_width / 2).saturating_sub(middle.chars().count() / 2);
+    let mut axis = format!("{}{}", left, " ".repeat(middle_start.saturating_sub(left.chars().count())));
+    axis.push_str(&middle);
+    let right_start = plot_width.saturating_sub(right.chars().count());
+    axis.push_str(&" ".repeat(right_start.saturating_sub(axis.chars().count())));
+    axis.push_str(&right);
+    println!("{:>width$}  {}", "", axis, width = label_width);
+
+    let legend: Vec<String> =
+        models.iter().zip(CURVE_GLYPHS).map(|(data, glyph)| format!("{} {}", glyph, data.model_number)).collect();
+    println!("\n{:>width$}  {}", "", legend.join("   "), width = label_width);
+    Ok(())
+}
+
+/// `lc interp`：单个型号在指定温度下的最大制热量
+pub fn print_interpolated_value(
+    machine_data: &MachineDatabase,
+    identifier: &str,
+    temp: f64,
+    force_interpolation: bool,
+    format: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let data = machine_data.get(identifier)
+        .ok_or_else(|| unknown_identifier_error(machine_data, identifier))?;
+    let result = data
+        .heating_capacity_detail(temp, force_interpolation)
+        .ok_or_else(|| format!("{} has no heating data points", data.model_number))?;
+
+    match format {
+        OutputFormat::Json => {
+            let report = serde_json::json!({
+                "model_number": data.model_number,
+                "temp": temp,
+                "btu_max": result.value,
+                "kind": result.kind,
+                "bracket": result.bracket,
+            });
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+        _ => println!("{:.0}", result.value),
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+struct CodeRow<'a> {
+    code: &'a str,
+    model_number: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    note: Option<String>,
+}
+
+// 同一个 machine code 被多条记录使用，或与其它型号的 model number 相同时，查找结果会被覆盖
+fn code_collision_note(machine_data: &MachineDatabase, data: &MachineData, code: &str) -> Option<String> {
+    if let Some(other) = machine_data.get(code).filter(|other| other.model_number == code && other.model_number != data.model_number) {
+        return Some(format!("collides with model {}", other.model_number));
+    }
+    let sharing = machine_data.models().filter(|other| other.machine_code.as_deref() == Some(code)).count();
+    if sharing > 1 {
+        return Some(format!("shared by {} models", sharing));
+    }
+    None
+}
+
+/// `lc codes`：机器代码与型号的对应表（--reverse 按型号排列）
+pub fn print_code_mapping(
+    machine_data: &MachineDatabase,
+    filter: Option<&str>,
+    reverse: bool,
+    format: OutputFormat,
+    table_options: TableOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let needle = filter.map(|f| f.to_lowercase());
+    let mut rows: Vec<CodeRow> = machine_data
+        .models()
+        .filter_map(|data| {
+            let code = data.machine_code.as_deref()?;
+            Some(CodeRow { code, model_number: &data.model_number, note: code_collision_note(machine_data, data, code) })
+        })
+        .filter(|row| {
+            needle.as_ref().is_none_or(|n| {
+                row.code.to_lowercase().contains(n) || row.model_number.to_lowercase().contains(n)
+            })
+        })
+        .collect();
+
+    if reverse {
+        rows.sort_by(|a, b| a.model_number.cmp(b.model_number));
+    } else {
+        rows.sort_by(|a, b| a.code.cmp(b.code));
+    }
+
+    fn columns<'a>(row: &CodeRow<'a>, reverse: bool) -> [&'a str; 2] {
+        if reverse { [row.model_number, row.code] } else { [row.code, row.model_number] }
+    }
+    let header = if reverse { ["Model", "Code"] } else { ["Code", "Model"] };
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(std::io::stdout());
+            writer.write_record([header[0], header[1], "Note"])?;
+            for row in &rows {
+                let [first, second] = columns(row, reverse);
+                writer.write_record([first, second, row.note.as_deref().unwrap_or("")])?;
+            }
+            writer.flush()?;
+        }
+        OutputFormat::Table | OutputFormat::Markdown => {
+            let mut table = create_format_table(format, table_options);
+            let show_notes = rows.iter().any(|row| row.note.is_some());
+            let mut header_cells = vec![Cell::new(header[0]), Cell::new(header[1])];
+            if show_notes {
+                header_cells.push(Cell::new("Note"));
+            }
+            table.set_header(header_cells);
+            for row in &rows {
+                let [first, second] = columns(row, reverse);
+                let mut cells = vec![Cell::new(first), Cell::new(second)];
+                if show_notes {
+                    cells.push(Cell::new(row.note.as_deref().unwrap_or("")).fg(Color::Yellow));
+                }
+                table.add_row(cells);
+            }
+            println!("{table}");
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct SuggestRow<'a> {
+    model_number: &'a str,
+    machine_code: Option<&'a str>,
+    btu_design_max: f64,
+    oversize_pct: f64,
+    retention_pct: Option<f64>,
+}
+
+fn print_suggestion_rows(
+    rows: &[SuggestRow],
+    design_temp: f64,
+    format: OutputFormat,
+    table_options: TableOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(rows)?),
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(std::io::stdout());
+            for row in rows {
+                writer.serialize(row)?;
+            }
+            writer.flush()?;
+        }
+        OutputFormat::Table | OutputFormat::Markdown => {
+            let mut table = create_format_table(format, table_options);
+            table.set_header(vec![
+                Cell::new("Model"),
+                Cell::new("Code"),
+                Cell::new(format!("Btu@{} max", design_temp)),
+                Cell::new("Oversize"),
+                Cell::new(format!("Retention @{}", design_temp)),
+            ]);
+            for row in rows {
+                table.add_row(vec![
+                    Cell::new(row.model_number),
+                    Cell::new(row.machine_code.unwrap_or("-")).set_alignment(CellAlignment::Center),
+                    Cell::new(format!("{:.0}", row.btu_design_max)).set_alignment(CellAlignment::Right),
+                    Cell::new(format!("{:.0}%", row.oversize_pct)).set_alignment(CellAlignment::Right),
+                    Cell::new(row.retention_pct.map(|r| format!("{:.0}%", r)).unwrap_or("-".to_string()))
+                        .set_alignment(CellAlignment::Right),
+                ]);
+            }
+            println!("{table}");
+        }
+    }
+    Ok(())
+}
+
+/// `lc suggest`：设计温度下能单独覆盖负荷、且超配不超过上限的型号
+pub fn suggest_single_unit(
+    machine_data: &MachineDatabase,
+    load: f64,
+    design_temp: f64,
+    max_oversize: f64,
+    target_oversize: f64,
+    format: OutputFormat,
+    table_options: TableOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if load <= 0.0 {
+        return Err("Load must be greater than 0".into());
+    }
+
+    let candidates: Vec<SuggestRow> = machine_data
+        .models()
+        .filter(|data| !data.heating_points().is_empty())
+        .map(|data| {
+            let capacity = data.calculate_heating_capacity_at_temp(design_temp);
+            SuggestRow {
+                model_number: &data.model_number,
+                machine_code: data.machine_code.as_deref(),
+                btu_design_max: capacity,
+                oversize_pct: capacity / load * 100.0,
+                retention_pct: data.retention_at_temp(design_temp).map(|r| r * 100.0),
+            }
+        })
+        .collect();
+
+    let (mut matches, rest): (Vec<SuggestRow>, Vec<SuggestRow>) = candidates
+        .into_iter()
+        .partition(|row| row.oversize_pct >= 100.0 && row.oversize_pct <= max_oversize);
+    matches.sort_by(|a, b| {
+        (a.oversize_pct - target_oversize).abs().total_cmp(&(b.oversize_pct - target_oversize).abs())
+    });
+
+    if !matches.is_empty() {
+        return print_suggestion_rows(&matches, design_temp, format, table_options);
+    }
+
+    // 没有合适的机型时，给出最接近的偏小和偏大机型
+    let closest_under = rest.iter().filter(|row| row.oversize_pct < 100.0).max_by(|a, b| a.oversize_pct.total_cmp(&b.oversize_pct));
+    let closest_over = rest.iter().filter(|row| row.oversize_pct > max_oversize).min_by(|a, b| a.oversize_pct.total_cmp(&b.oversize_pct));
+    let nearest: Vec<SuggestRow> = closest_under
+        .into_iter()
+        .chain(closest_over)
+        .cloned()
+        .collect();
+
+    eprintln!(
+        " No single unit covers {:.0} Btu at {}°F within 100%-{:.0}%; closest candidates:",
+        load, design_temp, max_oversize
+    );
+    print_suggestion_rows(&nearest, design_temp, format, table_options)
+}
+
+#[derive(Debug, Serialize)]
+struct CompareRow {
+    field: String,
+    first: Option<f64>,
+    second: Option<f64>,
+    delta: Option<f64>,
+}
+
+/// 差值保留两位小数并带符号，避免 9.2 - 10 这样的浮点尾数
+pub fn format_delta(delta: f64) -> String {
+    format!("{:+}", (delta * 100.0).round() / 100.0)
+}
+
+/// `lc compare`：两个型号的数值字段并排对比，附差值
+pub fn compare_machines(
+    machine_data: &MachineDatabase,
+    first: &str,
+    second: &str,
+    design_temp: f64,
+    format: OutputFormat,
+    table_options: TableOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let a = machine_data.get(first).ok_or_else(|| unknown_identifier_error(machine_data, first))?;
+    let b = machine_data.get(second).ok_or_else(|| unknown_identifier_error(machine_data, second))?;
+
+    let mut rows: Vec<CompareRow> = Vec::new();
+    let mut push_row = |field: String, first: Option<f64>, second: Option<f64>| {
+        let delta = match (first, second) {
+            (Some(x), Some(y)) => Some(y - x),
+            _ => None,
+        };
+        rows.push(CompareRow { field, first, second, delta });
+    };
+
+    for ((name, value_a), (_, value_b)) in a.numeric_fields().into_iter().zip(b.numeric_fields()) {
+        push_row(name.to_string(), value_a, value_b);
+    }
+
+    let mut temps = vec![design_temp];
+    for temp in [17.0, 5.0] {
+        if !temps.contains(&temp) { temps.push(temp); }
+    }
+    for temp in &temps {
+        let capacity = |data: &MachineData| {
+            Some(data.calculate_heating_capacity_at_temp(*temp)).filter(|_| !data.heating_points().is_empty())
+        };
+        push_row(format!("Btu@{} max", temp), capacity(a), capacity(b));
+    }
+    for temp in &temps {
+        let retention = |data: &MachineData| data.retention_at_temp(*temp).map(|r| r * 100.0);
+        push_row(format!("Retention @{} %", temp), retention(a), retention(b));
+    }
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(std::io::stdout());
+            for row in &rows {
+                writer.serialize(row)?;
+            }
+            writer.flush()?;
+        }
+        OutputFormat::Table | OutputFormat::Markdown => {
+            let mut table = create_format_table(format, table_options);
+            table.set_header(vec![
+                Cell::new("Field"),
+                Cell::new(&a.model_number),
+                Cell::new(&b.model_number),
+                Cell::new("Delta"),
+            ]);
+            let value_cell = |value: Option<f64>| {
+                Cell::new(value.map(|v| format!("{}", (v * 100.0).round() / 100.0)).unwrap_or("-".to_string()))
+                    .set_alignment(CellAlignment::Right)
+            };
+            for row in &rows {
+                let delta_cell = match row.delta {
+                    Some(d) if d > 0.0 => Cell::new(format_delta(d)).fg(Color::Green),
+                    Some(d) if d < 0.0 => Cell::new(format_delta(d)).fg(Color::Red),
+                    Some(d) => Cell::new(format_delta(d)),
+                    None => Cell::new("-"),
+                };
+                table.add_row(vec![
+                    Cell::new(&row.field),
+                    value_cell(row.first),
+                    value_cell(row.second),
+                    delta_cell.set_alignment(CellAlignment::Right),
+                ]);
+            }
+            println!("{table}");
+        }
+    }
+    Ok(())
+}
+
+/// --validate-db：列出占位记录、制热数据点不单调或不全，以及 EER 不合理的型号
+pub fn print_db_validation(machine_data: &MachineDatabase, table_options: TableOptions) {
+    print_section_title("CATALOG VALIDATION", Color::Blue, table_options);
+
+    let models: Vec<&MachineData> = machine_data.models().collect();
+    let placeholders: Vec<&str> = models
+        .iter()
+        .filter(|data| data.is_placeholder())
+        .map(|data| data.model_number.as_str())
+        .collect();
+    let implausible_eer: Vec<String> = models
+        .iter()
+        .filter_map(|data| Some(format!("{} ({:.3})", data.model_number, data.implausible_eer_95_rated()?)))
+        .collect();
+    // 占位记录已单独列出，不再算作数据不全
+    let non_monotonic: Vec<String> = models
+        .iter()
+        .filter_map(|data| {
+            let ((t1, v1), (t2, v2)) = data.decreasing_heating_segment()?;
+            Some(format!("{} ({}°F {:.0} > {}°F {:.0})", data.model_number, t1, v1, t2, v2))
+        })
+        .collect();
+    let incomplete: Vec<String> = models
+        .iter()
+        .filter(|data| !data.is_placeholder())
+        .filter_map(|data| {
+            let missing = data.missing_heating_temps();
+            let temps: Vec<String> = missing.iter().map(|t| format!("{}°F", t)).collect();
+            (!missing.is_empty()).then(|| format!("{} (no {})", data.model_number, temps.join("/")))
+        })
+        .collect();
+
+    let mut table = create_styled_table(table_options);
+    table.add_row(vec![
+        Cell::new("Models"),
+        Cell::new(models.len()).set_alignment(CellAlignment::Right),
+    ]);
+    table.add_row(vec![
+        Cell::new("Placeholder records"),
+        Cell::new(placeholders.len()).set_alignment(CellAlignment::Right),
+    ]);
+    table.add_row(vec![
+        Cell::new("Non-monotonic heating"),
+        Cell::new(non_monotonic.len()).set_alignment(CellAlignment::Right),
+    ]);
+    table.add_row(vec![
+        Cell::new("Incomplete heating points"),
+        Cell::new(incomplete.len()).set_alignment(CellAlignment::Right),
+    ]);
+    table.add_row(vec![
+        Cell::new("Implausible EER@95"),
+        Cell::new(implausible_eer.len()).set_alignment(CellAlignment::Right),
+    ]);
+    println!("{table}");
+
+    if !placeholders.is_empty() {
+        println!("\n Placeholders: {}", placeholders.join(", "));
+    }
+    if !non_monotonic.is_empty() {
+        println!("\n Non-monotonic heating: {}", non_monotonic.join(", "));
+    }
+    if !incomplete.is_empty() {
+        println!("\n Incomplete heating points: {}", incomplete.join(", "));
+    }
+    if !implausible_eer.is_empty() {
+        println!("\n Implausible EER@95 (ignored in project EER): {}", implausible_eer.join(", "));
+    }
+}
+
+/// 自检：对每个型号在其原始数据点上插值，结果必须等于该点的存储值
+pub fn run_self_test(machine_data: &MachineDatabase) -> Result<(), Box<dyn std::error::Error>> {
+    let models: Vec<&MachineData> = machine_data.models().collect();
+    let mut checked = 0;
+    let mut mismatches = Vec::new();
+
+    for data in &models {
+        for (temp, stored) in data.heating_points() {
+            let interpolated = data.interpolate_heating_capacity_at_temp(temp);
+            checked += 1;
+            if (interpolated - stored).abs() > 1e-6 * stored.abs().max(1.0) {
+                mismatches.push(format!(
+                    "{} @{}°F: stored {:.2}, interpolated {:.2}",
+                    data.model_number, temp, stored, interpolated
+                ));
+            }
+        }
+    }
+
+    if mismatches.is_empty() {
+        println!(" Self-test passed: {} points across {} models", checked, models.len());
+        Ok(())
+    } else {
+        for line in &mismatches {
+            eprintln!(" {}", line);
+        }
+        Err(format!("Self-test failed: {} of {} points mismatched", mismatches.len(), checked).into())
+    }
+}
@@ -0,0 +1,846 @@
+// 需要完整命令行参数（Cli）的子命令：check-load、quote、diff、batch、watch-dir、rename-check、check-project
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use comfy_table::{Attribute, Cell, CellAlignment, Color};
+use load_calculation::calc::{
+    AggregateBy, CalculationTotals, Recommendation, RequirementCheck, SortBy, perform_calculation,
+};
+use load_calculation::catalog::format_delta;
+use load_calculation::data::{MachineData, MachineDatabase, load_machine_data, suggest_identifiers, unknown_identifier_error};
+use load_calculation::input::{MachineSpec, canonical_scenario, parse_user_input, read_scenario_file};
+use load_calculation::render::{JsonReport, OutputFormat, create_format_table, create_styled_table};
+use serde::Serialize;
+
+use crate::{BatchFormat, Cli, QuoteFormat};
+
+// `lc check-load`：与主流程共用解析、降额和插值，只输出一行 PASS/FAIL；返回是否通过
+pub(crate) fn check_load(
+    cli: &Cli,
+    machine_data: &MachineDatabase,
+    machines: &[String],
+    load: f64,
+    min_coverage: f64,
+    max_coverage: Option<f64>,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    if load <= 0.0 {
+        return Err("--load must be greater than 0".into());
+    }
+    let user_input = parse_user_input(machines, cli.max_models)?;
+    let options = cli.calculation_options(machine_data)?.aggregate_by(AggregateBy::Model).sort_by(SortBy::Model);
+    let result = perform_calculation(&user_input, machine_data, &options)?;
+    let check = RequirementCheck::new(&result.totals, load);
+
+    let mut failures = Vec::new();
+    if !result.not_found.is_empty() {
+        let missing: Vec<&str> = result.not_found.iter().map(|m| m.identifier.as_str()).collect();
+        failures.push(format!("not found: {}", missing.join(", ")));
+    }
+    if check.oversize_pct < min_coverage {
+        failures.push(format!("below {}% minimum", min_coverage));
+    }
+    if let Some(max) = max_coverage
+        && check.oversize_pct > max
+    {
+        failures.push(format!("above {}% maximum", max));
+    }
+
+    let verdict = if failures.is_empty() { "PASS".to_string() } else { format!("FAIL ({})", failures.join("; ")) };
+    println!(
+        "{}: {:.*} Btu/h at {}°F vs load {:.*} Btu/h = {:.0}% coverage",
+        verdict,
+        cli.precision,
+        result.totals.total_btu_design_max,
+        cli.design_temp,
+        cli.precision,
+        load,
+        check.oversize_pct
+    );
+    Ok(failures.is_empty())
+}
+
+// 报价单中的一行设备
+struct QuoteLine {
+    description: String,
+    qty: u32,
+    unit_price: Option<f64>,
+}
+
+impl QuoteLine {
+    fn extended_price(&self) -> Option<f64> {
+        self.unit_price.map(|price| price * self.qty as f64)
+    }
+}
+
+// `lc quote` 的选项
+pub(crate) struct QuoteOptions<'a> {
+    pub(crate) tax_rate: Option<f64>,
+    pub(crate) quote_number: Option<&'a str>,
+    pub(crate) title: &'a str,
+    pub(crate) allow_missing_prices: bool,
+    pub(crate) format: QuoteFormat,
+}
+
+fn format_money(value: Option<f64>) -> String {
+    value.map(|v| format!("${:.2}", v)).unwrap_or_else(|| "TBD".to_string())
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// `lc quote`：面向客户的报价单，容量汇总只保留设计温度总制热量和推荐范围两行
+pub(crate) fn print_quote(
+    cli: &Cli,
+    machine_data: &MachineDatabase,
+    machines: &[String],
+    options: &QuoteOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let QuoteOptions { tax_rate, quote_number, title, allow_missing_prices, format } = *options;
+    let user_input = parse_user_input(machines, cli.max_models)?;
+    let calculation = cli.calculation_options(machine_data)?.aggregate_by(AggregateBy::Model).sort_by(SortBy::Model);
+    let result = perform_calculation(&user_input, machine_data, &calculation)?;
+    if let Some(missing) = result.not_found.first() {
+        return Err(unknown_identifier_error(machine_data, &missing.identifier).into());
+    }
+    let unpriced: Vec<&str> =
+        result.rows.iter().filter(|row| row.data.price.filter(|p| *p > 0.0).is_none()).map(|row| row.key.as_str()).collect();
+    if !unpriced.is_empty() && !allow_missing_prices {
+        return Err(format!(
+            "no catalog price for {} (use --allow-missing-prices to quote them as TBD)",
+            unpriced.join(", ")
+        )
+        .into());
+    }
+
+    let lines: Vec<QuoteLine> = result
+        .rows
+        .iter()
+        .map(|row| {
+            // 目录没有描述时退回到型号
+            let description = match row.data.description.as_deref().map(str::trim) {
+                Some(text) if !text.is_empty() => format!("{} - {}", row.data.model_number, text),
+                _ => row.data.model_number.clone(),
+            };
+            QuoteLine { description, qty: row.qty, unit_price: row.data.price.filter(|p| *p > 0.0) }
+        })
+        .collect();
+    let subtotal: f64 = lines.iter().filter_map(QuoteLine::extended_price).sum();
+    let tax = tax_rate.map(|rate| subtotal * rate / 100.0);
+    let total = subtotal + tax.unwrap_or(0.0);
+
+    let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let range = Recommendation::from_totals(&result.totals);
+    let capacity_line = format!(
+        "Total heating capacity at {}°F: {:.*} Btu/h",
+        cli.design_temp, cli.precision, result.totals.total_btu_design_max
+    );
+    let range_line = format!(
+        "Recommended load range: {:.*} - {:.*} Btu/h",
+        cli.precision, range.min, cli.precision, range.max
+    );
+    let mut totals_rows = vec![("Subtotal".to_string(), format_money(Some(subtotal)))];
+    if let (Some(rate), Some(tax)) = (tax_rate, tax) {
+        totals_rows.push((format!("Tax ({}%)", rate), format_money(Some(tax))));
+        totals_rows.push(("Total".to_string(), format_money(Some(total))));
+    }
+    let tbd_note = (!unpriced.is_empty()).then_some("Items priced TBD are not included in the subtotal.");
+
+    match format {
+        QuoteFormat::Table | QuoteFormat::Markdown => {
+            let markdown = format == QuoteFormat::Markdown;
+            if markdown {
+                println!("# {}\n", title);
+                if let Some(number) = quote_number {
+                    println!("**Quote #:** {}  ", number);
+                }
+                println!("**Date:** {}\n", date);
+            } else {
+                println!("{}", title);
+                if let Some(number) = quote_number {
+                    println!("Quote #: {}", number);
+                }
+                println!("Date: {}\n", date);
+            }
+
+            let mut table = create_format_table(if markdown { OutputFormat::Markdown } else { OutputFormat::Table }, cli.table_options());
+            table.set_header(vec!["Description", "Qty", "Unit Price", "Extended"]);
+            for line in &lines {
+                table.add_row(vec![
+                    Cell::new(&line.description),
+                    Cell::new(line.qty).set_alignment(CellAlignment::Right),
+                    Cell::new(format_money(line.unit_price)).set_alignment(CellAlignment::Right),
+                    Cell::new(format_money(line.extended_price())).set_alignment(CellAlignment::Right),
+                ]);
+            }
+            for (label, value) in &totals_rows {
+                let label = if markdown { format!("**{}**", label) } else { label.clone() };
+                table.add_row(vec![
+                    Cell::new(label).add_attribute(Attribute::Bold),
+                    Cell::new(""),
+                    Cell::new(""),
+                    Cell::new(value).set_alignment(CellAlignment::Right).add_attribute(Attribute::Bold),
+                ]);
+            }
+            println!("{table}");
+            if let Some(note) = tbd_note {
+                println!("\n{}", note);
+            }
+            let bullet = if markdown { "- " } else { "" };
+            println!("\n{}{}\n{}{}", bullet, capacity_line, bullet, range_line);
+        }
+        QuoteFormat::Html => {
+            println!("<h1>{}</h1>", escape_html(title));
+            if let Some(number) = quote_number {
+                println!("<p>Quote #: {}</p>", escape_html(number));
+            }
+            println!("<p>Date: {}</p>", date);
+            println!("<table>");
+            println!("  <thead><tr><th>Description</th><th>Qty</th><th>Unit Price</th><th>Extended</th></tr></thead>");
+            println!("  <tbody>");
+            for line in &lines {
+                println!(
+                    "    <tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    escape_html(&line.description),
+                    line.qty,
+                    format_money(line.unit_price),
+                    format_money(line.extended_price())
+                );
+            }
+            println!("  </tbody>");
+            println!("  <tfoot>");
+            for (label, value) in &totals_rows {
+                println!("    <tr><th colspan=\"3\">{}</th><td>{}</td></tr>", escape_html(label), value);
+            }
+            println!("  </tfoot>");
+            println!("</table>");
+            if let Some(note) = tbd_note {
+                println!("<p>{}</p>", note);
+            }
+            println!("<p>{}<br>{}</p>", escape_html(&capacity_line), escape_html(&range_line));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+struct DiffRow {
+    kind: &'static str,
+    name: String,
+    a: f64,
+    b: f64,
+    delta: f64,
+}
+
+pub(crate) fn diff_scenarios(
+    cli: &Cli,
+    machine_data: &MachineDatabase,
+    first: &[String],
+    second: &[String],
+    format: OutputFormat,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let (design_temp, precision) = (cli.design_temp, cli.precision);
+    let (items_a, items_b) = if second.is_empty() {
+        match first {
+            [file_a, file_b] => (read_scenario_file(file_a)?, read_scenario_file(file_b)?),
+            _ => return Err("diff expects two scenario files, or two machine lists separated by `--`".into()),
+        }
+    } else {
+        (first.to_vec(), second.to_vec())
+    };
+    let input_a = parse_user_input(&items_a, cli.max_models)?;
+    let input_b = parse_user_input(&items_b, cli.max_models)?;
+
+    let scenario_a = canonical_scenario(&input_a, machine_data)?;
+    let scenario_b = canonical_scenario(&input_b, machine_data)?;
+    let qty_of = |scenario: &[(String, u32)], key: &str| {
+        scenario.iter().find(|(k, _)| k == key).map(|(_, q)| *q).unwrap_or(0)
+    };
+    let mut keys: Vec<&String> = scenario_a.iter().chain(&scenario_b).map(|(k, _)| k).collect();
+    keys.sort();
+    keys.dedup();
+
+    let mut rows = Vec::new();
+    for key in keys {
+        let (a, b) = (qty_of(&scenario_a, key), qty_of(&scenario_b, key));
+        let kind = match (a, b) {
+            (0, _) => "added",
+            (_, 0) => "removed",
+            _ if a != b => "changed",
+            _ => continue,
+        };
+        rows.push(DiffRow { kind, name: key.clone(), a: a as f64, b: b as f64, delta: b as f64 - a as f64 });
+    }
+    let differs = !rows.is_empty();
+
+    // 与直接运行 lc 相同的计算参数（--derate、--force-interpolation 等）
+    let options = cli.calculation_options(machine_data)?.aggregate_by(AggregateBy::Model).sort_by(SortBy::Model);
+    let totals_a = perform_calculation(&input_a, machine_data, &options)?.totals;
+    let totals_b = perform_calculation(&input_b, machine_data, &options)?.totals;
+    let range_a = Recommendation::from_totals(&totals_a);
+    let range_b = Recommendation::from_totals(&totals_b);
+    let mut push_total = |kind: &'static str, name: String, a: f64, b: f64| {
+        rows.push(DiffRow { kind, name, a, b, delta: b - a });
+    };
+    push_total("total", "Btu @95 min".to_string(), totals_a.total_btu_95_min, totals_b.total_btu_95_min);
+    push_total("total", "Btu @95 rtd".to_string(), totals_a.total_btu_95_rated, totals_b.total_btu_95_rated);
+    push_total("total", "Btu @5  max".to_string(), totals_a.total_btu_5_max, totals_b.total_btu_5_max);
+    push_total("total", "Btu @17 max".to_string(), totals_a.total_btu_17_max, totals_b.total_btu_17_max);
+    push_total("total", "Btu @17 rtd".to_string(), totals_a.total_btu_17_rated, totals_b.total_btu_17_rated);
+    push_total("total", format!("Btu @{} max", design_temp), totals_a.total_btu_design_max, totals_b.total_btu_design_max);
+    push_total("total", "Price".to_string(), totals_a.total_price, totals_b.total_price);
+    push_total("recommendation", "Recommend min".to_string(), range_a.min, range_b.min);
+    push_total("recommendation", "Recommend mid".to_string(), range_a.mid, range_b.mid);
+    push_total("recommendation", "Recommend max".to_string(), range_a.max, range_b.max);
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(std::io::stdout());
+            for row in &rows {
+                writer.serialize(row)?;
+            }
+            writer.flush()?;
+        }
+        OutputFormat::Table | OutputFormat::Markdown => {
+            let (changes, totals): (Vec<&DiffRow>, Vec<&DiffRow>) =
+                rows.iter().partition(|row| matches!(row.kind, "added" | "removed" | "changed"));
+
+            if changes.is_empty() {
+                println!(" Scenarios contain the same machines");
+            } else {
+                let mut table = create_format_table(format, cli.table_options());
+                table.set_header(vec![Cell::new("Model"), Cell::new("A"), Cell::new("B"), Cell::new("Change")]);
+                for row in changes {
+                    let color = match row.kind {
+                        "added" => Color::Green,
+                        "removed" => Color::Red,
+                        _ => Color::Yellow,
+                    };
+                    table.add_row(vec![
+                        Cell::new(&row.name),
+                        Cell::new(row.a).set_alignment(CellAlignment::Right),
+                        Cell::new(row.b).set_alignment(CellAlignment::Right),
+                        Cell::new(row.kind).fg(color),
+                    ]);
+                }
+                println!("{table}");
+            }
+
+            let mut table = create_format_table(format, cli.table_options());
+            table.set_header(vec![Cell::new("Total"), Cell::new("A"), Cell::new("B"), Cell::new("Delta")]);
+            for row in totals {
+                let delta_cell = match row.delta {
+                    d if d > 0.0 => Cell::new(format_delta(d)).fg(Color::Green),
+                    d if d < 0.0 => Cell::new(format_delta(d)).fg(Color::Red),
+                    d => Cell::new(format_delta(d)),
+                };
+                table.add_row(vec![
+                    Cell::new(&row.name),
+                    Cell::new(format!("{:.*}", precision, row.a)).set_alignment(CellAlignment::Right),
+                    Cell::new(format!("{:.*}", precision, row.b)).set_alignment(CellAlignment::Right),
+                    delta_cell.set_alignment(CellAlignment::Right),
+                ]);
+            }
+            println!("{table}");
+        }
+    }
+    Ok(differs)
+}
+
+// 目录取其中所有非隐藏文件，否则按 glob 展开
+fn batch_inputs(source: &str) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let source_path = Path::new(source);
+    let mut files: Vec<PathBuf> = if source_path.is_dir() {
+        std::fs::read_dir(source_path)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_file())
+            .filter(|path| !path.file_name().is_some_and(|n| n.to_string_lossy().starts_with('.')))
+            .collect()
+    } else {
+        glob::glob(source)?.filter_map(Result::ok).filter(|path| path.is_file()).collect()
+    };
+    files.sort();
+    if files.is_empty() {
+        return Err(format!("no scenario files found in {}", source).into());
+    }
+    Ok(files)
+}
+
+#[derive(Debug, Serialize)]
+struct BatchLine<'a> {
+    file: String,
+    #[serde(flatten)]
+    report: JsonReport<'a>,
+}
+
+// 单个文件的计算；返回合计供 --summary 使用
+fn run_batch_file(
+    cli: &Cli,
+    machine_data: &MachineDatabase,
+    path: &Path,
+    output_dir: Option<&Path>,
+    format: BatchFormat,
+    strict: bool,
+) -> Result<CalculationTotals, Box<dyn std::error::Error>> {
+    let input = parse_user_input(&read_scenario_file(&path.to_string_lossy())?, cli.max_models)?;
+    let options = cli.calculation_options(machine_data)?;
+    let result = perform_calculation(&input, machine_data, &options)?;
+    if strict && !result.not_found.is_empty() {
+        let missing: Vec<&str> = result.not_found.iter().map(|m| m.identifier.as_str()).collect();
+        return Err(format!("unknown models: {}", missing.join(", ")).into());
+    }
+
+    let report = JsonReport::new(&result, cli.design_temp, cli.required);
+    match output_dir {
+        Some(dir) => {
+            let (extension, text) = match format {
+                BatchFormat::Json => ("json", serde_json::to_string_pretty(&report)? + "\n"),
+                BatchFormat::Yaml => ("yaml", serde_yaml::to_string(&report)?),
+            };
+            let stem = path.file_stem().unwrap_or_default();
+            let target = dir.join(stem).with_extension(extension);
+            std::fs::write(&target, text).map_err(|e| format!("failed to write {}: {}", target.display(), e))?;
+        }
+        None => {
+            let line = BatchLine { file: path.display().to_string(), report };
+            println!("{}", serde_json::to_string(&line)?);
+        }
+    }
+    Ok(result.totals)
+}
+
+pub(crate) fn run_batch(
+    cli: &Cli,
+    machine_data: &MachineDatabase,
+    source: &str,
+    output_dir: Option<&Path>,
+    format: BatchFormat,
+    strict: bool,
+    summary: bool,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let files = batch_inputs(source)?;
+    if let Some(dir) = output_dir {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let mut jobs = Vec::new();
+    let mut failed = 0;
+    for path in &files {
+        match run_batch_file(cli, machine_data, path, output_dir, format, strict) {
+            Ok(totals) => jobs.push((path, totals)),
+            Err(e) => {
+                failed += 1;
+                eprintln!("error: {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    if summary {
+        let mut table = create_styled_table(cli.table_options());
+        table.set_header(vec![
+            "File".to_string(),
+            "Units".to_string(),
+            format!("Btu@{} max", cli.design_temp),
+            "Recommend mid".to_string(),
+        ]);
+        let mut add_row = |label: String, totals: &CalculationTotals, bold: bool| {
+            let design_max = totals.total_btu_design_max;
+            let mut cells = vec![
+                Cell::new(label),
+                Cell::new(totals.total_units).set_alignment(CellAlignment::Right),
+                Cell::new(format!("{:.*}", cli.precision, design_max)).set_alignment(CellAlignment::Right),
+                Cell::new(format!("{:.*}", cli.precision, Recommendation::from_totals(totals).mid)).set_alignment(CellAlignment::Right),
+            ];
+            if bold {
+                cells = cells.into_iter().map(|cell| cell.add_attribute(Attribute::Bold)).collect();
+            }
+            table.add_row(cells);
+        };
+        for (path, totals) in &jobs {
+            let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            add_row(name, totals, false);
+        }
+        // 所有成功文件的合计
+        let portfolio = CalculationTotals {
+            total_btu_design_max: jobs.iter().map(|(_, totals)| totals.total_btu_design_max).sum(),
+            total_units: jobs.iter().map(|(_, totals)| totals.total_units).sum(),
+            ..Default::default()
+        };
+        add_row(format!("Total ({} files)", jobs.len()), &portfolio, true);
+        // JSONL 写在 stdout 时，汇总表改走 stderr
+        if output_dir.is_some() {
+            println!("{table}");
+        } else {
+            eprintln!("{table}");
+        }
+    }
+    eprintln!(" {} files processed, {} failed", files.len(), failed);
+    Ok(failed == 0)
+}
+
+// 沿 superseded_by 链找到最终的替代型号；链上出现循环或断在目录外时返回 Err(断点型号)
+fn final_replacement<'a>(machine_data: &'a MachineDatabase, data: &'a MachineData) -> Result<Option<&'a str>, String> {
+    let mut current = data;
+    let mut seen = vec![data.model_number.as_str()];
+    while let Some(next) = current.superseded_by.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        if seen.contains(&next) {
+            return Err(next.to_string());
+        }
+        current = machine_data.get(next).ok_or_else(|| next.to_string())?;
+        seen.push(&current.model_number);
+    }
+    Ok((current.model_number != data.model_number).then_some(current.model_number.as_str()))
+}
+
+// 替换一行中与 old 完全相同的 token，保留空白和 # 注释
+fn replace_token(line: &str, old: &str, new: &str) -> String {
+    let (code, comment) = match line.find('#') {
+        Some(index) => line.split_at(index),
+        None => (line, ""),
+    };
+    let mut out = String::new();
+    let mut rest = code;
+    while !rest.is_empty() {
+        let split = if rest.starts_with(char::is_whitespace) {
+            rest.find(|c: char| !c.is_whitespace())
+        } else {
+            rest.find(char::is_whitespace)
+        }
+        .unwrap_or(rest.len());
+        let (piece, tail) = rest.split_at(split);
+        out.push_str(if piece == old { new } else { piece });
+        rest = tail;
+    }
+    out + comment
+}
+
+// `lc rename-check`：每个问题一行 file:line；返回是否仍有无法解析的标识
+// --fix 改写前的备份：<file>.bak 已存在时依次改用 <file>.bak.1、<file>.bak.2……，不覆盖之前的备份
+fn write_backup(path: &Path, text: &str) -> Result<PathBuf, String> {
+    use std::io::Write;
+
+    let mut attempt = 0;
+    loop {
+        let backup = match attempt {
+            0 => PathBuf::from(format!("{}.bak", path.display())),
+            n => PathBuf::from(format!("{}.bak.{}", path.display(), n)),
+        };
+        match std::fs::OpenOptions::new().write(true).create_new(true).open(&backup) {
+            Ok(mut file) => {
+                file.write_all(text.as_bytes()).map_err(|e| format!("failed to write {}: {}", backup.display(), e))?;
+                return Ok(backup);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => attempt += 1,
+            Err(e) => return Err(format!("failed to write {}: {}", backup.display(), e)),
+        }
+    }
+}
+
+pub(crate) fn rename_check(
+    machine_data: &MachineDatabase,
+    files: &[PathBuf],
+    fix: bool,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let mut unresolved = 0;
+    for path in files {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("failed to read scenario {}: {}", path.display(), e))?;
+        let mut lines: Vec<String> = Vec::new();
+        let mut changed = false;
+        for (index, line) in text.lines().enumerate() {
+            let mut new_line = line.to_string();
+            let code = line.split('#').next().unwrap_or("");
+            for token in code.split_whitespace() {
+                let location = format!("{}:{}", path.display(), index + 1);
+                let spec: MachineSpec = match token.parse() {
+                    Ok(spec) => spec,
+                    Err(e) => {
+                        unresolved += 1;
+                        println!("{}: {}: {}", location, token, e);
+                        continue;
+                    }
+                };
+                let Some(data) = machine_data.get(&spec.identifier) else {
+                    unresolved += 1;
+                    let suggestions = suggest_identifiers(machine_data, &spec.identifier);
+                    match suggestions.first() {
+                        Some(suggestion) => println!("{}: {}: not found (did you mean {}?)", location, token, suggestion),
+                        None => println!("{}: {}: not found", location, token),
+                    }
+                    continue;
+                };
+                match final_replacement(machine_data, data) {
+                    Ok(None) => {}
+                    Ok(Some(replacement)) => {
+                        let new_token =
+                            if token == spec.identifier { replacement.to_string() } else { format!("{}x{}", replacement, spec.qty) };
+                        if fix {
+                            new_line = replace_token(&new_line, token, &new_token);
+                            changed = true;
+                            println!("{}: {}: superseded, replaced with {}", location, token, new_token);
+                        } else {
+                            println!("{}: {}: superseded by {} (use --fix to apply)", location, token, new_token);
+                        }
+                    }
+                    Err(broken) => {
+                        unresolved += 1;
+                        println!("{}: {}: superseded by {}, which is not in the catalog", location, token, broken);
+                    }
+                }
+            }
+            lines.push(new_line);
+        }
+        if changed {
+            let backup = write_backup(path, &text)?;
+            println!("{}: original saved as {}", path.display(), backup.display());
+            let mut output = lines.join("\n");
+            if text.ends_with('\n') {
+                output.push('\n');
+            }
+            std::fs::write(path, output).map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+        }
+    }
+    if unresolved > 0 {
+        eprintln!(" {} unresolved identifier(s)", unresolved);
+    }
+    Ok(unresolved > 0)
+}
+
+// watch-dir 的场景：.toml 可以单独指定设计温度和需求负荷
+struct WatchScenario {
+    machines: Vec<String>,
+    design_temp: Option<f64>,
+    required: Option<f64>,
+}
+
+fn read_watch_scenario(path: &Path) -> Result<WatchScenario, Box<dyn std::error::Error>> {
+    if path.extension().is_some_and(|ext| ext == "txt") {
+        let machines = read_scenario_file(&path.to_string_lossy())?;
+        return Ok(WatchScenario { machines, design_temp: None, required: None });
+    }
+    let text = std::fs::read_to_string(path)?;
+    let document: toml_edit::DocumentMut = text.parse()?;
+    let number = |key: &str| -> Result<Option<f64>, String> {
+        match document.get(key) {
+            None => Ok(None),
+            Some(item) => item
+                .as_float()
+                .or_else(|| item.as_integer().map(|i| i as f64))
+                .map(Some)
+                .ok_or_else(|| format!("`{}` must be a number", key)),
+        }
+    };
+    let machines = document
+        .get("machines")
+        .and_then(|item| item.as_array())
+        .ok_or("expected a `machines = [...]` array")?
+        .iter()
+        .map(|value| value.as_str().map(str::to_string).ok_or("`machines` entries must be strings"))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(WatchScenario { machines, design_temp: number("design_temp")?, required: number("required")? })
+}
+
+// 只处理 .txt / .toml；跳过隐藏文件、编辑器临时文件和自己写出的结果，避免循环触发
+fn is_watch_scenario(path: &Path) -> bool {
+    let Some(name) = path.file_name().map(|n| n.to_string_lossy()) else { return false };
+    if name.starts_with('.') || name.ends_with('~') || name.ends_with(".error.txt") || name.contains(".result.") {
+        return false;
+    }
+    path.extension().is_some_and(|ext| ext == "txt" || ext == "toml")
+}
+
+// a.txt -> a.result.json / a.error.txt
+fn watch_sibling(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}.{}", stem, suffix))
+}
+
+fn run_watch_file(
+    cli: &Cli,
+    machine_data: &MachineDatabase,
+    path: &Path,
+    format: BatchFormat,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let scenario = read_watch_scenario(path)?;
+    let input = parse_user_input(&scenario.machines, cli.max_models)?;
+    if input.is_empty() {
+        return Err("no machines listed".into());
+    }
+    let design_temp = scenario.design_temp.unwrap_or(cli.design_temp);
+    let options = cli.calculation_options(machine_data)?.design_temp(design_temp);
+    let result = perform_calculation(&input, machine_data, &options)?;
+    let report = JsonReport::new(&result, design_temp, scenario.required.or(cli.required));
+    let (suffix, text) = match format {
+        BatchFormat::Json => ("result.json", serde_json::to_string_pretty(&report)? + "\n"),
+        BatchFormat::Yaml => ("result.yaml", serde_yaml::to_string(&report)?),
+    };
+    let target = watch_sibling(path, suffix);
+    std::fs::write(&target, text).map_err(|e| format!("failed to write {}: {}", target.display(), e))?;
+    Ok(target)
+}
+
+pub(crate) fn watch_dir(
+    cli: &Cli,
+    machine_data: &MachineDatabase,
+    dir: &Path,
+    format: BatchFormat,
+    interval_ms: u64,
+    once: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !dir.is_dir() {
+        return Err(format!("{} is not a directory", dir.display()).into());
+    }
+    let result_suffix = match format {
+        BatchFormat::Json => "result.json",
+        BatchFormat::Yaml => "result.yaml",
+    };
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    // 结果或错误文件比场景新时视为已处理，重启后不会重复计算
+    let up_to_date = |path: &Path, stamp: std::time::SystemTime| {
+        [result_suffix, "error.txt"]
+            .iter()
+            .any(|suffix| modified(&watch_sibling(path, suffix)).is_some_and(|output| output >= stamp))
+    };
+
+    if !once {
+        eprintln!("Watching {} (Ctrl-C to stop)", dir.display());
+    }
+    // 上一轮看到的 (修改时间, 大小)；连续两轮不变才处理，编辑器保存过程中的中间状态会被跳过
+    let mut observed: HashMap<PathBuf, (std::time::SystemTime, u64)> = HashMap::new();
+    loop {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_file() && is_watch_scenario(path))
+            .collect();
+        files.sort();
+
+        let mut current = HashMap::new();
+        for path in files {
+            let Ok(metadata) = std::fs::metadata(&path) else { continue };
+            let Ok(stamp) = metadata.modified() else { continue };
+            let state = (stamp, metadata.len());
+            let stable = once || observed.get(&path) == Some(&state);
+            current.insert(path.clone(), state);
+            if !stable || up_to_date(&path, stamp) {
+                continue;
+            }
+            match run_watch_file(cli, machine_data, &path, format) {
+                Ok(target) => {
+                    let _ = std::fs::remove_file(watch_sibling(&path, "error.txt"));
+                    eprintln!("ok: {} -> {}", path.display(), target.display());
+                }
+                Err(e) => {
+                    let _ = std::fs::remove_file(watch_sibling(&path, result_suffix));
+                    let error_path = watch_sibling(&path, "error.txt");
+                    if let Err(write_error) = std::fs::write(&error_path, format!("{}\n", e)) {
+                        eprintln!("error: cannot write {}: {}", error_path.display(), write_error);
+                    }
+                    eprintln!("error: {}: {}", path.display(), e);
+                }
+            }
+        }
+        if once {
+            return Ok(());
+        }
+        observed = current;
+        std::thread::sleep(std::time::Duration::from_millis(interval_ms));
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ProjectIssue {
+    identifier: String,
+    model_number: String,
+    status: &'static str,
+    details: String,
+}
+
+// 比较报价时与当前目录中的容量点（btu_* 与 lowest_temp）
+fn capacity_changes(old: &MachineData, new: &MachineData) -> Vec<String> {
+    let format_value = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or("-".to_string());
+    old.numeric_fields()
+        .into_iter()
+        .zip(new.numeric_fields())
+        .filter(|((name, _), _)| name.starts_with("btu_") || *name == "lowest_temp")
+        .filter(|((_, a), (_, b))| a != b)
+        .map(|((name, a), (_, b))| format!("{}: {} -> {}", name, format_value(a), format_value(b)))
+        .collect()
+}
+
+pub(crate) fn check_project(
+    cli: &Cli,
+    current: &MachineDatabase,
+    file: &str,
+    baseline_file: Option<&Path>,
+    format: OutputFormat,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let baseline = load_machine_data(baseline_file, cli.catalog_format(), cli.catalog_schema()?.as_ref(), false, false)?;
+    let input = parse_user_input(&read_scenario_file(file)?, cli.max_models)?;
+    let mut identifiers: Vec<&String> = input.keys().collect();
+    identifiers.sort();
+
+    let mut issues = Vec::new();
+    for identifier in &identifiers {
+        let quoted = baseline.get(identifier);
+        let model_number = quoted.or_else(|| current.get(identifier)).map(|d| d.model_number.clone());
+        let Some(model_number) = model_number else {
+            issues.push(ProjectIssue {
+                identifier: identifier.to_string(),
+                model_number: "-".to_string(),
+                status: "unknown",
+                details: "not in baseline or current catalog".to_string(),
+            });
+            continue;
+        };
+        match (quoted, current.get(&model_number)) {
+            (_, None) => issues.push(ProjectIssue {
+                identifier: identifier.to_string(),
+                model_number,
+                status: "removed",
+                details: "no longer in the current catalog".to_string(),
+            }),
+            (Some(old), Some(new)) => {
+                let changes = capacity_changes(old, new);
+                if !changes.is_empty() {
+                    issues.push(ProjectIssue {
+                        identifier: identifier.to_string(),
+                        model_number,
+                        status: "changed",
+                        details: changes.join("; "),
+                    });
+                }
+            }
+            (None, Some(_)) => {}
+        }
+    }
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&issues)?),
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(std::io::stdout());
+            for issue in &issues {
+                writer.serialize(issue)?;
+            }
+            writer.flush()?;
+        }
+        OutputFormat::Table | OutputFormat::Markdown => {
+            if issues.is_empty() {
+                println!(" All {} quoted models are in the current catalog with unchanged capacity data", identifiers.len());
+            } else {
+                let mut table = create_format_table(format, cli.table_options());
+                table.set_header(vec!["Identifier", "Model", "Status", "Details"]);
+                for issue in &issues {
+                    let color = if issue.status == "changed" { Color::Yellow } else { Color::Red };
+                    table.add_row(vec![
+                        Cell::new(&issue.identifier),
+                        Cell::new(&issue.model_number),
+                        Cell::new(issue.status).fg(color),
+                        Cell::new(&issue.details),
+                    ]);
+                }
+                println!("{table}");
+            }
+        }
+    }
+    Ok(!issues.is_empty())
+}
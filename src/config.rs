@@ -0,0 +1,321 @@
+// 配置文件（LC_CONFIG 或 ~/.config/lc/config.toml）、型号别名以及 man 手册页
+use std::path::{Path, PathBuf};
+
+use clap::{CommandFactory, ValueEnum};
+use clap::parser::ValueSource;
+use comfy_table::{Cell, CellAlignment};
+use load_calculation::data::{MachineDatabase, unknown_identifier_error};
+use load_calculation::render::{Locale, TableOptions, create_styled_table, format_optional_btu};
+
+use crate::{AliasAction, Cli, ConfigAction};
+
+// 不对应命令行参数、但会影响 lc 的环境变量
+const EXTRA_ENV_VARS: &[(&str, &str)] = &[
+    ("NO_COLOR", "When set to a non-empty value, tables are printed without ANSI colors."),
+    ("LC_CONFIG", "Path of the config file (default: $XDG_CONFIG_HOME/lc/config.toml)."),
+    ("XDG_DATA_HOME", "Base directory of the --log-run history (default: ~/.local/share)."),
+];
+
+// ENVIRONMENT 一节由 clap 参数上的 env 元数据生成，避免与参数定义脱节
+fn render_environment_section(command: &clap::Command, out: &mut Vec<u8>) {
+    let mut text = String::from(".SH ENVIRONMENT\n");
+    for arg in command.get_arguments() {
+        if let Some(env) = arg.get_env() {
+            let help = arg.get_help().map(|h| h.to_string()).unwrap_or_default();
+            text.push_str(&format!(".TP\n\\fB{}\\fR\n{} (--{})\n", env.to_string_lossy(), help, arg.get_long().unwrap_or_default()));
+        }
+    }
+    for (name, help) in EXTRA_ENV_VARS {
+        text.push_str(&format!(".TP\n\\fB{}\\fR\n{}\n", name, help));
+    }
+    out.extend_from_slice(text.as_bytes());
+}
+
+pub(crate) fn write_man_pages(out_dir: Option<&Path>) -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::Write;
+
+    let mut command = Cli::command();
+    command.build();
+    let mut pages: Vec<(String, Vec<u8>)> = Vec::new();
+
+    let mut top = Vec::new();
+    clap_mangen::Man::new(command.clone()).render(&mut top)?;
+    render_environment_section(&command, &mut top);
+    pages.push(("lc".to_string(), top));
+
+    for sub in command.get_subcommands().filter(|sub| !sub.is_hide_set() && sub.get_name() != "help") {
+        let name = format!("lc-{}", sub.get_name());
+        let mut page = Vec::new();
+        clap_mangen::Man::new(sub.clone().display_name(name.clone()).bin_name(name.replace("-", " "))).render(&mut page)?;
+        pages.push((name, page));
+    }
+
+    match out_dir {
+        Some(dir) => {
+            std::fs::create_dir_all(dir)?;
+            for (name, page) in &pages {
+                let path = dir.join(format!("{}.1", name));
+                std::fs::write(&path, page).map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+            }
+            eprintln!(" wrote {} man pages to {}", pages.len(), dir.display());
+        }
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            for (_, page) in &pages {
+                stdout.write_all(page)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// 配置文件可设置的键：(配置键, 对应的命令行参数 id, 环境变量)
+const CONFIG_KEYS: &[(&str, &str, Option<&str>)] = &[
+    ("design_temp", "design_temp", Some("LC_DESIGN_TEMP")),
+    ("data_file", "data_file", Some("LC_DATA_FILE")),
+    ("skip_placeholders", "skip_placeholders", None),
+    ("precision", "precision", None),
+    ("capacity_unit_label", "capacity_unit_label", None),
+    ("locale", "locale", None),
+    ("history", "log_run", Some("LC_LOG_RUN")),
+];
+
+fn config_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("LC_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("lc").join("config.toml"))
+}
+
+fn config_key(key: &str) -> Result<&'static (&'static str, &'static str, Option<&'static str>), String> {
+    CONFIG_KEYS.iter().find(|(name, _, _)| *name == key).ok_or_else(|| {
+        let names: Vec<&str> = CONFIG_KEYS.iter().map(|(name, _, _)| *name).collect();
+        format!("Unknown config key: {} (expected one of {})", key, names.join(", "))
+    })
+}
+
+// 配置文件不存在时返回空文档
+fn read_config_document(path: &Path) -> Result<toml_edit::DocumentMut, Box<dyn std::error::Error>> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => Ok(text.parse().map_err(|e| format!("invalid config file {}: {}", path.display(), e))?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(toml_edit::DocumentMut::new()),
+        Err(e) => Err(format!("failed to read config file {}: {}", path.display(), e).into()),
+    }
+}
+
+fn config_value_string(item: &toml_edit::Item) -> Option<String> {
+    let value = item.as_value()?;
+    match value {
+        toml_edit::Value::String(s) => Some(s.value().clone()),
+        toml_edit::Value::Integer(i) => Some(i.value().to_string()),
+        toml_edit::Value::Float(f) => Some(f.value().to_string()),
+        toml_edit::Value::Boolean(b) => Some(b.value().to_string()),
+        _ => None,
+    }
+}
+
+// 把配置值当作对应的命令行参数解析一遍，与命令行使用同一套校验
+fn validate_config_value(key: &str, value: &str) -> Result<(), String> {
+    let (_, arg_id, _) = config_key(key)?;
+    let command = Cli::command();
+    let arg = command.get_arguments().find(|arg| arg.get_id() == *arg_id).ok_or("missing argument")?;
+    if !arg.get_action().takes_values() {
+        return value.parse::<bool>().map(|_| ()).map_err(|_| format!("Invalid value for {}: expected true or false", key));
+    }
+    let flag = format!("--{}={}", arg.get_long().ok_or("missing argument")?, value);
+    Cli::command()
+        .try_get_matches_from(["lc", "--validate-db", &flag])
+        .map(|_| ())
+        .map_err(|e| {
+            let message = e.to_string();
+            let first_line = message.lines().next().unwrap_or_default().trim_start_matches("error: ").to_string();
+            format!("Invalid config value for {}: {}", key, first_line)
+        })
+}
+
+fn apply_config_value(cli: &mut Cli, key: &str, value: &str) -> Result<(), String> {
+    validate_config_value(key, value)?;
+    match key {
+        "design_temp" => cli.design_temp = value.parse().map_err(|_| "invalid design_temp")?,
+        "data_file" => cli.data_file = Some(PathBuf::from(value)),
+        "skip_placeholders" => cli.skip_placeholders = value == "true",
+        "precision" => cli.precision = value.parse().map_err(|_| "invalid precision")?,
+        "capacity_unit_label" => cli.capacity_unit_label = value.to_string(),
+        "locale" => cli.locale = Locale::from_str(value, true)?,
+        "history" => cli.log_run = value == "true",
+        _ => {}
+    }
+    Ok(())
+}
+
+// 配置文件只填补用户没有在命令行或环境变量中给出的参数
+pub(crate) fn apply_config_file(cli: &mut Cli, matches: &clap::ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(path) = config_path() else { return Ok(()) };
+    let document = read_config_document(&path)?;
+    for (key, arg_id, _) in CONFIG_KEYS {
+        let explicit = !matches!(matches.value_source(arg_id), None | Some(ValueSource::DefaultValue));
+        if explicit {
+            continue;
+        }
+        if let Some(value) = document.get(key).and_then(config_value_string) {
+            apply_config_value(cli, key, &value).map_err(|e| format!("{} (in {})", e, path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+// 配置文件 [aliases] 表中的 (别名, 目标) 列表
+fn config_aliases() -> Result<Vec<(String, String)>, Box<dyn std::error::Error>> {
+    let Some(path) = config_path() else { return Ok(Vec::new()) };
+    let document = read_config_document(&path)?;
+    let Some(table) = document.get("aliases").and_then(|item| item.as_table_like()) else {
+        return Ok(Vec::new());
+    };
+    Ok(table
+        .iter()
+        .filter_map(|(name, item)| item.as_str().map(|target| (name.to_string(), target.to_string())))
+        .collect())
+}
+
+// 别名与机器代码一样作为额外的键指向目标记录；目标已不在目录中的别名忽略
+pub(crate) fn insert_aliases(machine_data: &mut MachineDatabase) -> Result<(), Box<dyn std::error::Error>> {
+    for (name, target) in config_aliases()? {
+        if machine_data.contains_model(&name) || machine_data.contains_code(&name) {
+            continue;
+        }
+        machine_data.insert_alias(&name, &target);
+    }
+    Ok(())
+}
+
+pub(crate) fn run_alias(
+    machine_data: &MachineDatabase,
+    action: &AliasAction,
+    table_options: TableOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let path = config_path().ok_or("cannot determine config directory")?;
+    let mut document = read_config_document(&path)?;
+    let aliases = config_aliases()?;
+
+    match action {
+        AliasAction::Add { name, target } => {
+            if aliases.iter().any(|(existing, _)| existing == name) {
+                return Err(format!("Alias {} already exists", name).into());
+            }
+            if machine_data.contains_model(name) || machine_data.contains_code(name) {
+                return Err(format!("Alias {} would shadow an existing model or machine code", name).into());
+            }
+            let data = machine_data.get(target).ok_or_else(|| unknown_identifier_error(machine_data, target))?;
+            let table = document
+                .entry("aliases")
+                .or_insert(toml_edit::table())
+                .as_table_like_mut()
+                .ok_or("aliases in the config file is not a table")?;
+            table.insert(name, toml_edit::value(data.model_number.clone()));
+            write_config_document(&path, &document)?;
+            println!(" {} -> {} ({})", name, data.model_number, path.display());
+        }
+        AliasAction::Rm { name } => {
+            let removed = document
+                .get_mut("aliases")
+                .and_then(|item| item.as_table_like_mut())
+                .and_then(|table| table.remove(name));
+            if removed.is_none() {
+                return Err(format!("No alias named {} in {}", name, path.display()).into());
+            }
+            write_config_document(&path, &document)?;
+            println!(" removed alias {} ({})", name, path.display());
+        }
+        AliasAction::List => {
+            let mut table = create_styled_table(table_options);
+            table.set_header(vec!["Alias", "Target", "Btu@47 max"]);
+            for (name, target) in &aliases {
+                let btu_47_max = machine_data.get(target).and_then(|data| data.btu_47_max);
+                table.add_row(vec![
+                    Cell::new(name),
+                    Cell::new(target),
+                    Cell::new(format_optional_btu(btu_47_max)).set_alignment(CellAlignment::Right),
+                ]);
+            }
+            println!("{table}");
+        }
+    }
+    Ok(())
+}
+
+fn toml_value(value: &str) -> toml_edit::Value {
+    if let Ok(b) = value.parse::<bool>() {
+        b.into()
+    } else if let Ok(i) = value.parse::<i64>() {
+        i.into()
+    } else if let Ok(f) = value.parse::<f64>() {
+        f.into()
+    } else {
+        value.into()
+    }
+}
+
+pub(crate) fn run_config(action: Option<&ConfigAction>, table_options: TableOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let path = config_path().ok_or("cannot determine config directory")?;
+    let mut document = read_config_document(&path)?;
+
+    match action {
+        Some(ConfigAction::Get { key }) => {
+            config_key(key)?;
+            match document.get(key).and_then(config_value_string) {
+                Some(value) => println!("{}", value),
+                None => return Err(format!("{} is not set in {}", key, path.display()).into()),
+            }
+        }
+        Some(ConfigAction::Set { key, value }) => {
+            validate_config_value(key, value)?;
+            document[key.as_str()] = toml_edit::value(toml_value(value));
+            write_config_document(&path, &document)?;
+            println!(" {} = {} ({})", key, value, path.display());
+        }
+        Some(ConfigAction::Unset { key }) => {
+            config_key(key)?;
+            document.remove(key);
+            write_config_document(&path, &document)?;
+            println!(" {} unset ({})", key, path.display());
+        }
+        None => {
+            println!(" Config file: {}", path.display());
+            let mut command = Cli::command();
+            command.build();
+            let mut table = create_styled_table(table_options);
+            table.set_header(vec!["Key", "Value", "Source"]);
+            for (key, arg_id, env) in CONFIG_KEYS {
+                let env_value = env.and_then(|name| std::env::var(name).ok());
+                let file_value = document.get(key).and_then(config_value_string);
+                let (value, source) = if let Some(value) = env_value {
+                    (value, format!("env ({})", env.unwrap_or_default()))
+                } else if let Some(value) = file_value {
+                    (value, "file".to_string())
+                } else {
+                    let default = command
+                        .get_arguments()
+                        .find(|arg| arg.get_id() == *arg_id)
+                        .and_then(|arg| arg.get_default_values().first().map(|v| v.to_string_lossy().to_string()))
+                        .unwrap_or("-".to_string());
+                    (default, "default".to_string())
+                };
+                table.add_row(vec![Cell::new(key), Cell::new(value), Cell::new(source)]);
+            }
+            println!("{table}");
+        }
+    }
+    Ok(())
+}
+
+fn write_config_document(path: &Path, document: &toml_edit::DocumentMut) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, document.to_string())
+        .map_err(|e| format!("failed to write config file {}: {}", path.display(), e))?;
+    Ok(())
+}
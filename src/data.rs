@@ -0,0 +1,1376 @@
+//! 设备目录：MachineData 记录、目录 CSV 的读取（分隔符、小数点、区间写法）以及按型号/代码的查找
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::LazyLock;
+
+use clap::ValueEnum;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::calc::{Interpolation, InterpolationKind, interpolate_points};
+use crate::diagnostics;
+use crate::error::LcError;
+
+/// 编译进二进制的默认设备目录
+pub const CSV_DATA: &str = include_str!("../data/equipmentInfo.csv");
+
+/// 可信的 95°F 额定 EER 范围：实际设备大约在 8 到 30 之间；低于 1（功率栏填了容量，结果约 0.001）
+/// 或高于 40 只能是目录错列或单位填错，不能计入项目 EER
+pub const PLAUSIBLE_EER: std::ops::RangeInclusive<f64> = 1.0..=40.0;
+
+fn deserialize_f64_custom<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let opt_s: Result<Option<String>, _> = Deserialize::deserialize(deserializer);
+    match opt_s {
+        Ok(Some(s)) => {
+            let trimmed = s.trim();
+            if trimmed.is_empty() { return Ok(None); }
+            let format = PARSE_FORMAT.get();
+            match parse_catalog_number(trimmed, format.decimal_separator) {
+                Some(v) => if v > -90000.0 { Ok(Some(v)) } else { Ok(None) },
+                None => Ok(parse_range_value(trimmed, format)),
+            }
+        },
+        Ok(None) => Ok(None),
+        Err(_) => Ok(None),
+    }
+}
+
+// 与系统 locale 无关的数字解析：小数点由 --decimal-separator 决定，另一个符号（以及空格）
+// 只在标准的三位分组位置上当作千位分隔符，例如 "1,200.5" 或 "1.200,5"
+fn parse_catalog_number(text: &str, decimal: DecimalSeparator) -> Option<f64> {
+    let decimal = decimal.as_char();
+    if decimal == '.'
+        && let Ok(v) = text.parse::<f64>()
+    {
+        return Some(v);
+    }
+    let grouping = if decimal == '.' { ',' } else { '.' };
+    let compact: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    let (int_part, frac_part) = match compact.split_once(decimal) {
+        Some((int_part, frac_part)) => (int_part, Some(frac_part)),
+        None => (compact.as_str(), None),
+    };
+    let (sign, digits) = match int_part.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", int_part),
+    };
+    let groups: Vec<&str> = digits.split(grouping).collect();
+    let all_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let grouped_ok = groups.len() == 1
+        || (groups[0].len() <= 3 && groups[1..].iter().all(|g| g.len() == 3));
+    if !groups.iter().all(|g| all_digits(g)) || !grouped_ok || frac_part.is_some_and(|f| !all_digits(f)) {
+        return None;
+    }
+    format!("{}{}.{}", sign, groups.concat(), frac_part.unwrap_or("0")).parse().ok()
+}
+
+// 区间写法 "18000-21000"（也接受 – 和 ~），按 --range-basis 取下限、中点或上限
+fn parse_range_value(text: &str, format: CatalogFormat) -> Option<f64> {
+    static RANGE_RE: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^(\d[\d.,]*)\s*[-–~]\s*(\d[\d.,]*)$").unwrap());
+    let caps = RANGE_RE.captures(text)?;
+    let decimal = format.decimal_separator;
+    let (a, b) = (parse_catalog_number(&caps[1], decimal)?, parse_catalog_number(&caps[2], decimal)?);
+    let (low, high) = if a <= b { (a, b) } else { (b, a) };
+    Some(match format.range_basis {
+        RangeBasis::Min => low,
+        RangeBasis::Mid => (low + high) / 2.0,
+        RangeBasis::Max => high,
+    })
+}
+
+/// 目录 CSV 的分隔符、小数点和区间取值（--csv-delimiter / --decimal-separator / --range-basis）
+///
+/// 只用于 --data-file 指定的文件；内置目录总是按 [`CatalogFormat::default`]（逗号分隔、小数点为 "."、
+/// 区间取中点）读取，与命令行参数无关
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CatalogFormat {
+    pub delimiter: u8,
+    pub decimal_separator: DecimalSeparator,
+    pub range_basis: RangeBasis,
+}
+
+impl CatalogFormat {
+    const STANDARD: CatalogFormat =
+        CatalogFormat { delimiter: b',', decimal_separator: DecimalSeparator::Dot, range_basis: RangeBasis::Mid };
+}
+
+impl Default for CatalogFormat {
+    fn default() -> Self {
+        CatalogFormat::STANDARD
+    }
+}
+
+thread_local! {
+    // 正在解析的目录的格式：deserialize_with 的函数无法传参，由 with_catalog_format 在解析期间设置
+    static PARSE_FORMAT: std::cell::Cell<CatalogFormat> = const { std::cell::Cell::new(CatalogFormat::STANDARD) };
+}
+
+fn with_catalog_format<T>(format: CatalogFormat, parse: impl FnOnce() -> T) -> T {
+    let previous = PARSE_FORMAT.replace(format);
+    let result = parse();
+    PARSE_FORMAT.set(previous);
+    result
+}
+
+// 返回的行号偏移是表头之前跳过的行数；csv 的 position().line() 加上它才是文件中的行号
+fn catalog_reader(csv_text: &str, format: CatalogFormat) -> (csv::Reader<&[u8]>, u64) {
+    let (rest, skipped_lines) = skip_leading_comments(csv_text);
+    let reader = csv::ReaderBuilder::new()
+        .delimiter(format.delimiter)
+        .from_reader(rest.as_bytes());
+    (reader, skipped_lines)
+}
+
+/// --csv-delimiter：单个 ASCII 字符，或 "tab"
+pub fn parse_csv_delimiter(value: &str) -> Result<u8, String> {
+    match value {
+        "tab" | "\\t" | "\t" => Ok(b'\t'),
+        _ if value.len() == 1 && value.is_ascii() => Ok(value.as_bytes()[0]),
+        _ => Err(format!("expected a single ASCII character or \"tab\", got {:?}", value)),
+    }
+}
+
+fn deserialize_ahri<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    // 直接按整数字符串解析，避免经过 f64 丢失大编号的精度
+    let opt_s: Result<Option<String>, _> = Deserialize::deserialize(deserializer);
+    match opt_s {
+        Ok(Some(s)) => {
+            // AHRI 目录导出的编号带千分位逗号，如 "1,234,567,890"
+            let cleaned: String = s.trim().chars().filter(|c| *c != ',').collect();
+            let trimmed = cleaned.as_str();
+            // Excel 导出的 "211497152.0" 之类带零小数的写法
+            let digits = match trimmed.split_once('.') {
+                Some((int_part, frac)) if frac.chars().all(|c| c == '0') => int_part,
+                Some(_) => return Ok(None),
+                None => trimmed,
+            };
+            match digits.parse::<u64>() {
+                Ok(v) if v > 0 => Ok(Some(v)),
+                _ => Ok(None),
+            }
+        },
+        _ => Ok(None),
+    }
+}
+
+/// 目录中的一条设备记录；读取时字段名对应目录 CSV 的表头，输出（JSON/YAML）时使用字段名本身，空值省略
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct MachineData {
+    #[serde(rename(deserialize = "model number"))]
+    pub model_number: String,
+    
+    #[serde(rename(deserialize = "machine code"), skip_serializing_if = "Option::is_none")]
+    pub machine_code: Option<String>,
+
+    #[serde(rename(deserialize = "Description"), default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    
+    #[serde(rename(deserialize = "AHRI"), deserialize_with = "deserialize_ahri", skip_serializing_if = "Option::is_none")]
+    pub ahri: Option<u64>,
+
+    #[serde(rename(deserialize = "AHRI Cert Date"), default, skip_serializing_if = "Option::is_none")]
+    pub ahri_cert_date: Option<String>,
+
+    #[serde(rename(deserialize = "Btu@95min"), deserialize_with = "deserialize_f64_custom", skip_serializing_if = "Option::is_none")]
+    pub btu_95_min: Option<f64>,
+
+    // Heating points for interpolation
+    #[serde(rename(deserialize = "Btu@lowest max"), deserialize_with = "deserialize_f64_custom", skip_serializing_if = "Option::is_none")]
+    pub btu_lowest_max: Option<f64>,
+
+    #[serde(rename(deserialize = "lowest temperature"), deserialize_with = "deserialize_f64_custom", skip_serializing_if = "Option::is_none")]
+    pub lowest_temp: Option<f64>,
+
+    #[serde(rename(deserialize = "Btu@5max"), deserialize_with = "deserialize_f64_custom", skip_serializing_if = "Option::is_none")]
+    pub btu_5_max: Option<f64>,
+
+    #[serde(rename(deserialize = "Btu@17max"), deserialize_with = "deserialize_f64_custom", skip_serializing_if = "Option::is_none")]
+    pub btu_17_max: Option<f64>,
+
+    #[serde(rename(deserialize = "Btu@17rated"), deserialize_with = "deserialize_f64_custom", skip_serializing_if = "Option::is_none")]
+    pub btu_17_rated: Option<f64>,
+
+    #[serde(rename(deserialize = "Btu@47max"), deserialize_with = "deserialize_f64_custom", skip_serializing_if = "Option::is_none")]
+    pub btu_47_max: Option<f64>,
+
+    // Rated points, used as fallback when the max point is missing
+    #[serde(rename(deserialize = "Btu@lowest rated"), default, deserialize_with = "deserialize_f64_custom", skip_serializing_if = "Option::is_none")]
+    pub btu_lowest_rated: Option<f64>,
+
+    #[serde(rename(deserialize = "Btu@5rated"), default, deserialize_with = "deserialize_f64_custom", skip_serializing_if = "Option::is_none")]
+    pub btu_5_rated: Option<f64>,
+
+    #[serde(rename(deserialize = "Btu@47rated"), default, deserialize_with = "deserialize_f64_custom", skip_serializing_if = "Option::is_none")]
+    pub btu_47_rated: Option<f64>,
+
+    // COP at max capacity, for --show-cop
+    #[serde(rename(deserialize = "COP@lowest max"), default, deserialize_with = "deserialize_f64_custom", skip_serializing_if = "Option::is_none")]
+    pub cop_lowest_max: Option<f64>,
+
+    #[serde(rename(deserialize = "COP@5max"), default, deserialize_with = "deserialize_f64_custom", skip_serializing_if = "Option::is_none")]
+    pub cop_5_max: Option<f64>,
+
+    #[serde(rename(deserialize = "COP@17max"), default, deserialize_with = "deserialize_f64_custom", skip_serializing_if = "Option::is_none")]
+    pub cop_17_max: Option<f64>,
+
+    #[serde(rename(deserialize = "COP@47max"), default, deserialize_with = "deserialize_f64_custom", skip_serializing_if = "Option::is_none")]
+    pub cop_47_max: Option<f64>,
+
+    // For Loan
+    #[serde(rename(deserialize = "Btu@95rated"), deserialize_with = "deserialize_f64_custom", skip_serializing_if = "Option::is_none")]
+    pub btu_95_rated: Option<f64>,
+
+    #[serde(rename(deserialize = "kW@95rated"), default, deserialize_with = "deserialize_f64_custom", skip_serializing_if = "Option::is_none")]
+    pub kw_95_rated: Option<f64>,
+
+    #[serde(rename(deserialize = "HSPF"), deserialize_with = "deserialize_f64_custom", skip_serializing_if = "Option::is_none")]
+    pub hspf: Option<f64>,
+
+    #[serde(rename(deserialize = "SEER"), deserialize_with = "deserialize_f64_custom", skip_serializing_if = "Option::is_none")]
+    pub seer: Option<f64>,
+
+    #[serde(rename(deserialize = "Price"), default, deserialize_with = "deserialize_f64_custom", skip_serializing_if = "Option::is_none")]
+    pub price: Option<f64>,
+
+    // 停产型号的替代型号，供 `lc rename-check` 使用
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub superseded_by: Option<String>,
+}
+
+impl MachineData {
+    /// 所有 Btu 字段都为空的占位记录（例如只有型号的室内机行）
+    pub fn is_placeholder(&self) -> bool {
+        [
+            self.btu_95_min,
+            self.btu_95_rated,
+            self.btu_lowest_max,
+            self.btu_lowest_rated,
+            self.btu_5_max,
+            self.btu_5_rated,
+            self.btu_17_max,
+            self.btu_17_rated,
+            self.btu_47_max,
+            self.btu_47_rated,
+        ]
+        .iter()
+        .all(|v| v.is_none())
+    }
+
+    /// 插值使用的 (温度, Btu) 点，按温度升序；缺少 max 点时用同温度的 rated 点代替
+    ///
+    /// 供需要原始数据点的调用方（例如自行拟合曲线）使用，与插值计算取到的点完全相同。
+    /// 每个温度只有一个点：最低温度与 5/17/47°F 重合时采用该温度列的值。
+    pub fn heating_points(&self) -> Vec<(f64, f64)> {
+        let mut points = Vec::new();
+
+        if let Some(val) = self.btu_5_max.or(self.btu_5_rated) { points.push((5.0, val)); }
+        if let Some(val) = self.btu_17_max.or(self.btu_17_rated) { points.push((17.0, val)); }
+        if let Some(val) = self.btu_47_max.or(self.btu_47_rated) { points.push((47.0, val)); }
+        // 最低温度读成 NaN（目录里写了 "NaN"）时这个点没有意义，直接忽略
+        if let (Some(temp), Some(val)) = (self.lowest_temp, self.btu_lowest_max.or(self.btu_lowest_rated))
+            && !temp.is_nan()
+        {
+             points.push((temp, val));
+        }
+
+        sorted_unique_points(points)
+    }
+
+    /// 第一段制热量随温度升高而下降的相邻数据点 ((t1, v1), (t2, v2))；数据单调时为 None
+    pub fn decreasing_heating_segment(&self) -> Option<((f64, f64), (f64, f64))> {
+        self.heating_points()
+            .windows(2)
+            .map(|pair| (pair[0], pair[1]))
+            .find(|((_, v1), (_, v2))| v2 < v1)
+    }
+
+    /// 5/17/47°F 中 max 和 rated 都为空的温度点
+    pub fn missing_heating_temps(&self) -> Vec<f64> {
+        [
+            (5.0, self.btu_5_max.or(self.btu_5_rated)),
+            (17.0, self.btu_17_max.or(self.btu_17_rated)),
+            (47.0, self.btu_47_max.or(self.btu_47_rated)),
+        ]
+        .into_iter()
+        .filter(|(_, value)| value.is_none())
+        .map(|(temp, _)| temp)
+        .collect()
+    }
+
+    /// 有制热数据的最低温度（°F），即型号标称能运行到的温度；没有任何制热数据时为 None
+    pub fn lowest_operating_temp(&self) -> Option<f64> {
+        self.heating_points().first().map(|(temp, _)| *temp)
+    }
+
+    /// 最低温度与 5/17/47°F 某列重合但制热量不同：返回 (温度, 最低温度列的值, 采用的该温度列的值)
+    pub fn conflicting_lowest_point(&self) -> Option<(f64, f64, f64)> {
+        let temp = self.lowest_temp?;
+        let lowest = self.btu_lowest_max.or(self.btu_lowest_rated)?;
+        let used = self.heating_points().into_iter().find(|(t, _)| *t == temp)?.1;
+        (used != lowest).then_some((temp, lowest, used))
+    }
+
+    /// 指定温度下的最大制热量（数据点上取原值，其余插值或外推）；没有制热数据时为 0
+    pub fn calculate_heating_capacity_at_temp(&self, target_temp: f64) -> f64 {
+        self.heating_capacity_detail(target_temp, false).map(|r| r.value).unwrap_or(0.0)
+    }
+
+    /// 始终走插值路径（不做数据点精确匹配），用于 --force-interpolation
+    pub fn interpolate_heating_capacity_at_temp(&self, target_temp: f64) -> f64 {
+        self.interpolate_heating_detail(target_temp).map(|r| r.value).unwrap_or(0.0)
+    }
+
+    /// 计算指定温度下的最大制热量并记录计算方式；没有任何制热数据点时返回 None
+    pub fn heating_capacity_detail(&self, target_temp: f64, force_interpolation: bool) -> Option<Interpolation> {
+        // 正好落在数据点上时直接取该点的值
+        if !force_interpolation
+            && let Some(&(temp, val)) = self.heating_points().iter().find(|(temp, _)| *temp == target_temp)
+        {
+            return Some(Interpolation { value: val, kind: InterpolationKind::Exact, bracket: [(temp, val), (temp, val)] });
+        }
+        self.interpolate_heating_detail(target_temp)
+    }
+
+    fn interpolate_heating_detail(&self, target_temp: f64) -> Option<Interpolation> {
+        interpolate_points(&self.heating_points(), target_temp)
+    }
+
+    // COP 数据点（与最大制热量曲线对应的 COP@max），按温度升序
+    fn collect_cop_points(&self) -> Vec<(f64, f64)> {
+        let mut points = Vec::new();
+
+        if let Some(val) = self.cop_5_max { points.push((5.0, val)); }
+        if let Some(val) = self.cop_17_max { points.push((17.0, val)); }
+        if let Some(val) = self.cop_47_max { points.push((47.0, val)); }
+        if let (Some(temp), Some(val)) = (self.lowest_temp, self.cop_lowest_max)
+            && !temp.is_nan()
+        {
+             points.push((temp, val));
+        }
+
+        sorted_unique_points(points)
+    }
+
+    /// 指定温度下插值得到的 COP；没有 COP 数据时返回 None
+    pub fn cop_at_temp(&self, target_temp: f64) -> Option<f64> {
+        interpolate_points(&self.collect_cop_points(), target_temp).map(|r| r.value)
+    }
+
+    /// 指定温度下最大制热量相对 47°F 的保持率
+    pub fn retention_at_temp(&self, target_temp: f64) -> Option<f64> {
+        let btu_47 = self.btu_47_max.filter(|v| *v > 0.0)?;
+        Some(self.calculate_heating_capacity_at_temp(target_temp) / btu_47)
+    }
+
+    /// 95°F 额定制冷 EER = Btu/h ÷ 输入功率 W；缺少容量或功率时为 None
+    /// 超出 [`PLAUSIBLE_EER`] 的值同样视为没有数据，见 [`MachineData::implausible_eer_95_rated`]
+    pub fn eer_95_rated(&self) -> Option<f64> {
+        self.raw_eer_95_rated().filter(|eer| PLAUSIBLE_EER.contains(eer))
+    }
+
+    /// 算得出但超出 [`PLAUSIBLE_EER`] 的 EER（通常是错列：功率栏填了容量），供警告和 --validate-db 使用
+    pub fn implausible_eer_95_rated(&self) -> Option<f64> {
+        self.raw_eer_95_rated().filter(|eer| !PLAUSIBLE_EER.contains(eer))
+    }
+
+    fn raw_eer_95_rated(&self) -> Option<f64> {
+        let capacity = self.btu_95_rated.filter(|v| *v > 0.0)?;
+        let kw = self.kw_95_rated.filter(|v| *v > 0.0)?;
+        Some(capacity / (kw * 1000.0))
+    }
+
+    /// 展示用的标识：machine code 比 model number 短时用 machine code，否则用 model number
+    pub fn display_name(&self) -> String {
+        match self.machine_code.as_deref().map(str::trim) {
+            Some(code) if !code.is_empty() && code.chars().count() < self.model_number.chars().count() => code.to_string(),
+            _ => self.model_number.clone(),
+        }
+    }
+
+    /// 所有数值字段（名称，值），用于 info / 对比等展示
+    pub fn numeric_fields(&self) -> Vec<(&'static str, Option<f64>)> {
+        vec![
+            ("btu_95_min", self.btu_95_min),
+            ("btu_95_rated", self.btu_95_rated),
+            ("kw_95_rated", self.kw_95_rated),
+            ("btu_47_max", self.btu_47_max),
+            ("btu_47_rated", self.btu_47_rated),
+            ("btu_17_max", self.btu_17_max),
+            ("btu_17_rated", self.btu_17_rated),
+            ("btu_5_max", self.btu_5_max),
+            ("btu_5_rated", self.btu_5_rated),
+            ("btu_lowest_max", self.btu_lowest_max),
+            ("btu_lowest_rated", self.btu_lowest_rated),
+            ("lowest_temp", self.lowest_temp),
+            ("hspf", self.hspf),
+            ("seer", self.seer),
+            ("price", self.price),
+        ]
+    }
+
+    /// 设计温度下每 kBtu 的价格；目录没有价格（或价格为 0）时返回 None
+    pub fn price_per_kbtu_at_temp(&self, target_temp: f64) -> Option<f64> {
+        let price = self.price.filter(|p| *p > 0.0)?;
+        let capacity = self.calculate_heating_capacity_at_temp(target_temp);
+        if capacity > 0.0 { Some(price / (capacity / 1000.0)) } else { None }
+    }
+}
+
+/// 测试用：复制一条记录并替换其中若干数值字段
+#[cfg(test)]
+pub struct MachineDataOverride {
+    pub data: MachineData,
+}
+
+#[cfg(test)]
+macro_rules! override_setters {
+    ($($field:ident),* $(,)?) => {
+        $(
+            pub fn $field(mut self, value: Option<f64>) -> Self {
+                self.data.$field = value;
+                self
+            }
+        )*
+    };
+}
+
+#[cfg(test)]
+impl MachineDataOverride {
+    override_setters!(
+        btu_95_min,
+        btu_95_rated,
+        kw_95_rated,
+        btu_lowest_max,
+        btu_lowest_rated,
+        lowest_temp,
+        btu_5_max,
+        btu_5_rated,
+        btu_17_max,
+        btu_17_rated,
+        btu_47_max,
+        btu_47_rated,
+        cop_lowest_max,
+        cop_5_max,
+        cop_17_max,
+        cop_47_max,
+        hspf,
+        seer,
+        price,
+    );
+
+    pub fn build(self) -> MachineData {
+        self.data
+    }
+}
+
+#[cfg(test)]
+impl MachineData {
+    /// `m.cloned_with_override().btu_47_max(Some(30000.0)).build()`
+    pub fn cloned_with_override(&self) -> MachineDataOverride {
+        MachineDataOverride { data: self.clone() }
+    }
+}
+
+/// 目录中数字的小数点（--decimal-separator）
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecimalSeparator {
+    /// 1,200.5
+    #[value(alias = ".")]
+    Dot,
+    /// 1.200,5
+    #[value(alias = ",")]
+    Comma,
+}
+
+impl DecimalSeparator {
+    fn as_char(self) -> char {
+        match self {
+            DecimalSeparator::Dot => '.',
+            DecimalSeparator::Comma => ',',
+        }
+    }
+}
+
+/// 目录中区间写法取哪个值（--range-basis）
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RangeBasis {
+    /// Lower end of the range
+    Min,
+    /// Midpoint of the range
+    Mid,
+    /// Upper end of the range
+    Max,
+}
+
+// 只用来取出 MachineData 的字段名（即 #[serde(rename)] 之后的列名），不读取任何数据
+struct ColumnNames(&'static [&'static str]);
+
+impl<'de> serde::Deserializer<'de> for &mut ColumnNames {
+    type Error = serde::de::value::Error;
+
+    fn deserialize_any<V: serde::de::Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(serde::de::Error::custom("only struct field names are collected"))
+    }
+
+    fn deserialize_struct<V: serde::de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.0 = fields;
+        Err(serde::de::Error::custom("field names collected"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option unit
+        unit_struct newtype_struct seq tuple tuple_struct map enum identifier ignored_any
+    }
+}
+
+/// 反序列化器期望的目录列名，按 MachineData 的字段顺序；与 CSV 表头逐字比较
+pub fn catalog_columns() -> &'static [&'static str] {
+    let mut names = ColumnNames(&[]);
+    let _ = MachineData::deserialize(&mut names);
+    names.0
+}
+
+/// `lc template`：只有表头的空白目录；示例行（KM18H5O）以 # 注释的形式放在表头之前的元数据中，
+/// 因为表头之后的行不会当作注释跳过
+pub fn catalog_template() -> String {
+    let example = |column: &str| match column {
+        "model number" => "KM18H5O",
+        "Description" => "KINGHOME MULTIZONE ODU 208-230V 60Hz 18k Btu",
+        "AHRI" => "211497152",
+        "Btu@95min" => "7000",
+        "Btu@lowest max" => "10200",
+        "lowest temperature" => "-22",
+        "Btu@5max" => "13600",
+        "Btu@17max" => "16400",
+        "Btu@17rated" => "15000",
+        "Btu@47max" => "18100",
+        "Btu@5rated" => "13600",
+        "Btu@47rated" => "18000",
+        "COP@lowest max" => "1.51",
+        "COP@5max" => "2.21",
+        "COP@17max" => "2.64",
+        "COP@47max" => "3.21",
+        "Btu@95rated" => "17000",
+        "kW@95rated" => "1.41",
+        "HSPF" => "10",
+        "SEER" => "21",
+        "Price" => "799.7",
+        _ => "",
+    };
+    let columns = catalog_columns();
+    let row = |cell: &dyn Fn(&str) -> String| columns.iter().map(|c| cell(c)).collect::<Vec<_>>().join(",");
+    let quote = |text: &str| if text.contains([',', '"']) { format!("\"{}\"", text.replace('"', "\"\"")) } else { text.to_string() };
+    format!(
+        "# example row: copy it below the header without the leading \"# \"\n# {}\n{}\n",
+        row(&|c| quote(example(c))),
+        row(&|c| quote(c))
+    )
+}
+
+// 解析后的内置目录，见 embedded_database
+static EMBEDDED_DATABASE: std::sync::OnceLock<MachineDatabase> = std::sync::OnceLock::new();
+
+/// 内置目录只在第一次访问时按 [`CatalogFormat::default`] 解析，之后每次返回同一份；不读取任何全局设置
+pub fn embedded_database() -> Result<&'static MachineDatabase, LcError> {
+    if let Some(database) = EMBEDDED_DATABASE.get() {
+        return Ok(database);
+    }
+    let database = parse_machine_data(CSV_DATA, "embedded catalog", CatalogFormat::default(), false, false)?;
+    Ok(EMBEDDED_DATABASE.get_or_init(|| database))
+}
+
+/// 读取目录：指定了文件时按 `format` 从文件读取并用 `schema` 检查，否则使用内置目录
+/// （复制 [`embedded_database`]，不重新解析，也不检查 `schema`）
+///
+/// `strict` 时型号为空的行直接报错，否则改用机器代码作为型号
+pub fn load_machine_data(
+    data_file: Option<&Path>,
+    format: CatalogFormat,
+    schema: Option<&CatalogSchema>,
+    skip_placeholders: bool,
+    strict: bool,
+) -> Result<MachineDatabase, LcError> {
+    match data_file {
+        Some(path) => {
+            let text = std::fs::read_to_string(path).map_err(|e| LcError::DataLoad {
+                source: e.into(),
+                path: path.display().to_string(),
+                line: None,
+            })?;
+            if let Some(schema) = schema {
+                schema.validate(&text, &path.display().to_string(), format)?;
+            }
+            parse_machine_data(&text, &path.display().to_string(), format, skip_placeholders, strict)
+        }
+        // strict 检查的是原始行，只能重新解析
+        None if strict => parse_machine_data(CSV_DATA, "embedded catalog", CatalogFormat::default(), skip_placeholders, strict),
+        None => {
+            let mut database = embedded_database()?.clone();
+            if skip_placeholders {
+                database.retain(|record| !record.is_placeholder());
+            }
+            Ok(database)
+        }
+    }
+}
+
+/// --check-csv-encoding：报告第一个非 UTF-8 字节的位置（例如 Excel 导出的 Windows-1252 文件）
+pub fn check_csv_encoding(path: &Path) -> Result<(), LcError> {
+    let data_load = |source: Box<dyn std::error::Error + Send + Sync>, line: Option<u64>| LcError::DataLoad {
+        source,
+        path: path.display().to_string(),
+        line,
+    };
+    let bytes = std::fs::read(path).map_err(|e| data_load(e.into(), None))?;
+    if let Err(e) = std::str::from_utf8(&bytes) {
+        let offset = e.valid_up_to();
+        let line = bytes[..offset].iter().filter(|b| **b == b'\n').count() as u64 + 1;
+        let message = format!(
+            "not valid UTF-8: invalid byte 0x{:02X} at offset {}; re-save the file as UTF-8",
+            bytes[offset], offset
+        );
+        return Err(data_load(message.into(), Some(line)));
+    }
+    Ok(())
+}
+
+// 列名比较时忽略大小写和空白："Btu@lowest max" 与 "btu@lowestmax" 视为同一列
+fn normalize_column(name: &str) -> String {
+    name.chars().filter(|c| !c.is_whitespace()).flat_map(char::to_lowercase).collect()
+}
+
+/// --require-columns：读取目录表头，一次列出所有缺少的列
+pub fn check_required_columns(data_file: Option<&Path>, format: CatalogFormat, required: &[String]) -> Result<(), LcError> {
+    let source_name = data_file.map_or_else(|| "embedded catalog".to_string(), |path| path.display().to_string());
+    let data_load = |source: Box<dyn std::error::Error + Send + Sync>, line: Option<u64>| LcError::DataLoad {
+        source,
+        path: source_name.clone(),
+        line,
+    };
+    let (text, format) = match data_file {
+        Some(path) => (std::fs::read_to_string(path).map_err(|e| data_load(e.into(), None))?, format),
+        None => (CSV_DATA.to_string(), CatalogFormat::default()),
+    };
+    let (mut reader, line_offset) = catalog_reader(&text, format);
+    let headers = reader.headers().map_err(|e| {
+        let line = e.position().map(|p| p.line() + line_offset);
+        data_load(e.into(), line)
+    })?;
+    let header: Vec<String> = headers.iter().map(normalize_column).collect();
+    let missing: Vec<&str> = required
+        .iter()
+        .map(|name| name.trim())
+        .filter(|name| !name.is_empty() && !header.contains(&normalize_column(name)))
+        .collect();
+    if !missing.is_empty() {
+        let line = headers.position().map(|p| p.line() + line_offset);
+        return Err(data_load(format!("missing required column(s): {}", missing.join(", ")).into(), line));
+    }
+    Ok(())
+}
+
+/// --schema：外部目录的列定义，在读取 --data-file 时逐行检查
+///
+/// TOML 中三个列名数组均可省略：
+///
+/// ```toml
+/// required = ["model number", "AHRI", "Btu@17max"]  # 表头必须有，且每行都不能为空
+/// optional = ["Price"]                              # 可以缺少或留空；给出时表头不能有 required 和 optional 以外的列
+/// numeric = ["AHRI", "Btu@17max", "Price"]          # 非空单元格必须是数字（或区间）
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CatalogSchema {
+    pub required: Vec<String>,
+    // None 表示没有 optional 键，表头中的其它列不受限制
+    pub optional: Option<Vec<String>>,
+    pub numeric: Vec<String>,
+}
+
+impl CatalogSchema {
+    /// 解析 TOML 列定义；未知的键视为拼写错误
+    pub fn from_toml(text: &str) -> Result<Self, String> {
+        let document: toml_edit::DocumentMut = text.parse().map_err(|e| format!("invalid schema: {}", e))?;
+        let mut schema = CatalogSchema::default();
+        for (key, item) in document.iter() {
+            let columns = match key {
+                "required" => &mut schema.required,
+                "optional" => schema.optional.insert(Vec::new()),
+                "numeric" => &mut schema.numeric,
+                _ => return Err(format!("unknown schema key `{}` (expected required, optional or numeric)", key)),
+            };
+            *columns = item
+                .as_array()
+                .ok_or_else(|| format!("schema key `{}` must be an array of column names", key))?
+                .iter()
+                .map(|value| value.as_str().map(str::to_string).ok_or_else(|| format!("`{}` entries must be strings", key)))
+                .collect::<Result<_, _>>()?;
+        }
+        Ok(schema)
+    }
+
+    /// 从文件读取列定义
+    pub fn load(path: &Path) -> Result<Self, LcError> {
+        let data_load = |source: Box<dyn std::error::Error + Send + Sync>| LcError::DataLoad {
+            source,
+            path: path.display().to_string(),
+            line: None,
+        };
+        let text = std::fs::read_to_string(path).map_err(|e| data_load(e.into()))?;
+        Self::from_toml(&text).map_err(|e| data_load(e.into()))
+    }
+
+    /// 检查表头和每一行；第一个不符合的位置作为 [`LcError::DataLoad`] 返回（带行号）
+    pub fn validate(&self, csv_text: &str, source_name: &str, format: CatalogFormat) -> Result<(), LcError> {
+        let data_load = |message: String, line: Option<u64>| LcError::DataLoad {
+            source: message.into(),
+            path: source_name.to_string(),
+            line,
+        };
+        let (mut reader, line_offset) = catalog_reader(csv_text, format);
+        let csv_error = |e: csv::Error| {
+            let line = e.position().map(|p| p.line() + line_offset);
+            LcError::DataLoad { source: e.into(), path: source_name.to_string(), line }
+        };
+        let headers = reader.headers().map_err(csv_error)?.clone();
+        let column_index = |name: &str| headers.iter().position(|h| normalize_column(h) == normalize_column(name));
+
+        let missing: Vec<&str> = self.required.iter().filter(|name| column_index(name).is_none()).map(String::as_str).collect();
+        let header_line = headers.position().map(|p| p.line() + line_offset);
+        if !missing.is_empty() {
+            return Err(data_load(format!("missing required column(s): {}", missing.join(", ")), header_line));
+        }
+        if let Some(optional) = &self.optional {
+            let declared = |header: &str| self.required.iter().chain(optional).any(|name| normalize_column(name) == normalize_column(header));
+            let unexpected: Vec<&str> = headers.iter().filter(|header| !declared(header)).collect();
+            if !unexpected.is_empty() {
+                return Err(data_load(format!("column(s) not in the schema: {}", unexpected.join(", ")), header_line));
+            }
+        }
+        let required: Vec<(&str, usize)> = self.required.iter().filter_map(|name| Some((name.as_str(), column_index(name)?))).collect();
+        let numeric: Vec<(&str, usize)> = self.numeric.iter().filter_map(|name| Some((name.as_str(), column_index(name)?))).collect();
+
+        for result in reader.records() {
+            let row = result.map_err(csv_error)?;
+            let line = row.position().map(|p| p.line() + line_offset);
+            let cell = |index: usize| row.get(index).unwrap_or("").trim();
+            if let Some((name, _)) = required.iter().find(|(_, index)| cell(*index).is_empty()) {
+                return Err(data_load(format!("required column `{}` is empty", name), line));
+            }
+            for &(name, index) in &numeric {
+                let value = cell(index);
+                if !value.is_empty() && parse_catalog_number(value, format.decimal_separator)
+                    .or_else(|| parse_range_value(value, format))
+                    .is_none() {
+                    return Err(data_load(format!("column `{}` is not a number: {:?}", name, value), line));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// 跳过表头之前以 # 开头的元数据行（来源、导出日期等）以及空行
+fn skip_leading_comments(csv_text: &str) -> (&str, u64) {
+    let mut rest = csv_text.trim_start_matches('\u{feff}');
+    let mut skipped_lines = 0;
+    while rest.starts_with('#') || rest.starts_with('\n') || rest.starts_with("\r\n") {
+        rest = rest.split_once('\n').map(|(_, tail)| tail).unwrap_or("");
+        skipped_lines += 1;
+    }
+    (rest, skipped_lines)
+}
+
+/// 按 `format` 从 CSV 文本解析目录；`source_name` 只用于错误信息
+pub fn parse_machine_data(
+    csv_text: &str,
+    source_name: &str,
+    format: CatalogFormat,
+    skip_placeholders: bool,
+    strict: bool,
+) -> Result<MachineDatabase, LcError> {
+    let data_load = |source: Box<dyn std::error::Error + Send + Sync>, line: Option<u64>| LcError::DataLoad {
+        source,
+        path: source_name.to_string(),
+        line,
+    };
+    let (mut reader, line_offset) = catalog_reader(csv_text, format);
+    let csv_error = |e: csv::Error| {
+        let line = e.position().map(|p| p.line() + line_offset);
+        data_load(e.into(), line)
+    };
+    let headers = reader.headers().map_err(csv_error)?.clone();
+    let mut data_map = MachineDatabase::default();
+
+    for result in reader.records() {
+        let row = result.map_err(csv_error)?;
+        let mut record: MachineData =
+            with_catalog_format(format, || row.deserialize(Some(&headers))).map_err(csv_error)?;
+        // 型号为空时改用机器代码作为键，避免空键记录互相覆盖；两者都为空的行无法引用，直接跳过
+        if record.model_number.trim().is_empty() {
+            let line = row.position().map(|p| p.line() + line_offset);
+            let code = record.machine_code.as_deref().map(str::trim).filter(|c| !c.is_empty());
+            match code {
+                _ if strict => return Err(data_load("blank model number".into(), line)),
+                Some(code) => record.model_number = code.to_string(),
+                None => continue,
+            }
+        }
+        if skip_placeholders && record.is_placeholder() { continue; }
+        if let Some((temp, lowest, used)) = record.conflicting_lowest_point() {
+            diagnostics::warn(
+                "duplicate_point",
+                Some(&record.model_number),
+                format!(
+                    "{}: lowest temperature point ({}°F, {}) duplicates the {}°F column ({}); using the {}°F column",
+                    record.model_number, temp, lowest, temp, used, temp
+                ),
+            );
+        }
+        data_map.insert(record);
+    }
+
+    // 空目录（空文件或只有表头）直接报错，避免所有型号都显示 NOT FOUND
+    if data_map.is_empty() {
+        return Err(data_load("catalog loaded 0 models".into(), None));
+    }
+    Ok(data_map)
+}
+
+// 按温度升序排序并去掉重复温度：稳定排序，同一温度保留先加入的点，
+// 否则插值可能取到宽度为 0 的区间或两个重复点之间任意一对
+fn sorted_unique_points(mut points: Vec<(f64, f64)>) -> Vec<(f64, f64)> {
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    points.dedup_by(|later, earlier| later.0 == earlier.0);
+    points
+}
+
+/// 设备目录：每条记录只保存一次，机器代码和别名只是指向型号的额外键
+///
+/// [`get`](Self::get) 的查找顺序：model number → machine code → 别名 → AHRI 编号（标识全为数字时）
+#[derive(Debug, Clone, Default)]
+pub struct MachineDatabase {
+    // 按 model number 排序，models() 的顺序即列表顺序
+    records: BTreeMap<String, MachineData>,
+    codes: HashMap<String, String>,
+    aliases: HashMap<String, String>,
+}
+
+impl MachineDatabase {
+    /// 加入一条记录；model number 相同的旧记录被替换
+    pub fn insert(&mut self, record: MachineData) {
+        if let Some(old_code) = self.records.get(&record.model_number).and_then(|old| old.machine_code.clone())
+            && self.codes.get(&old_code) == Some(&record.model_number)
+        {
+            self.codes.remove(&old_code);
+        }
+        if let Some(code) = &record.machine_code {
+            self.codes.insert(code.clone(), record.model_number.clone());
+        }
+        self.records.insert(record.model_number.clone(), record);
+    }
+
+    /// 只保留满足条件的记录，同时去掉指向被删记录的机器代码和别名
+    pub fn retain(&mut self, mut keep: impl FnMut(&MachineData) -> bool) {
+        self.records.retain(|_, record| keep(record));
+        let records = &self.records;
+        self.codes.retain(|_, model| records.contains_key(model));
+        self.aliases.retain(|_, model| records.contains_key(model));
+    }
+
+    /// 从目录文件重新读取全部记录；读取失败时保持原内容不变
+    ///
+    /// 别名和 --virtual 机器不在文件中，需要调用方重新加入
+    pub fn reload_from(
+        &mut self,
+        path: &Path,
+        format: CatalogFormat,
+        schema: Option<&CatalogSchema>,
+        skip_placeholders: bool,
+        strict: bool,
+    ) -> Result<(), LcError> {
+        *self = load_machine_data(Some(path), format, schema, skip_placeholders, strict)?;
+        Ok(())
+    }
+
+    /// 加入别名；`target` 可以是任何 [`get`](Self::get) 能解析的标识，解析不到时返回 false
+    pub fn insert_alias(&mut self, alias: &str, target: &str) -> bool {
+        let Some(model) = self.get(target).map(|data| data.model_number.clone()) else { return false };
+        self.aliases.insert(alias.to_string(), model);
+        true
+    }
+
+    /// 按 model number、machine code、别名、AHRI 编号的顺序查找
+    pub fn get(&self, identifier: &str) -> Option<&MachineData> {
+        self.records
+            .get(identifier)
+            .or_else(|| self.codes.get(identifier).and_then(|model| self.records.get(model)))
+            .or_else(|| self.aliases.get(identifier).and_then(|model| self.records.get(model)))
+            .or_else(|| self.by_ahri(identifier.parse().ok()?).into_iter().next())
+    }
+
+    /// 每条记录恰好一次，按 model number 排序
+    pub fn models(&self) -> impl Iterator<Item = &MachineData> {
+        self.records.values()
+    }
+
+    /// 使用该 AHRI 编号的所有型号（系统搭配可能共用同一个 AHRI 编号）
+    pub fn by_ahri(&self, ahri: u64) -> Vec<&MachineData> {
+        self.models().filter(|data| data.ahri == Some(ahri)).collect()
+    }
+
+    /// 记录数（不含机器代码与别名）
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// `model` 是某条记录的型号（不匹配机器代码或别名）
+    pub fn contains_model(&self, model: &str) -> bool {
+        self.records.contains_key(model)
+    }
+
+    /// `code` 是某条记录的机器代码（不匹配型号或别名）
+    pub fn contains_code(&self, code: &str) -> bool {
+        self.codes.contains_key(code)
+    }
+
+    /// 所有可用于查找的标识：型号、机器代码和别名
+    pub fn identifiers(&self) -> impl Iterator<Item = &str> {
+        self.records.keys().chain(self.codes.keys()).chain(self.aliases.keys()).map(String::as_str)
+    }
+}
+
+impl FromIterator<MachineData> for MachineDatabase {
+    fn from_iter<I: IntoIterator<Item = MachineData>>(iter: I) -> Self {
+        let mut database = MachineDatabase::default();
+        for record in iter {
+            database.insert(record);
+        }
+        database
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b_chars.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut curr = vec![i + 1; b_chars.len() + 1];
+        for (j, cb) in b_chars.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        prev = curr;
+    }
+    prev[b_chars.len()]
+}
+
+/// 找不到型号时给出最接近的几个 model number / machine code
+pub fn suggest_identifiers(machine_data: &MachineDatabase, identifier: &str) -> Vec<String> {
+    let needle = identifier.to_uppercase();
+    let max_distance = (needle.chars().count() / 3).max(2);
+
+    let mut candidates: Vec<(usize, &str)> = machine_data
+        .identifiers()
+        .map(|key| (edit_distance(&needle, &key.to_uppercase()), key))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    candidates.sort();
+    candidates.into_iter().take(3).map(|(_, key)| key.to_string()).collect()
+}
+
+/// 找不到标识时的错误，附带最接近的候选
+pub fn unknown_identifier_error(machine_data: &MachineDatabase, identifier: &str) -> LcError {
+    LcError::UnknownIdentifier {
+        token: identifier.to_string(),
+        suggestions: suggest_identifiers(machine_data, identifier),
+    }
+}
+
+/// 一次列出所有找不到的标识，每个都附带候选
+pub fn unknown_identifiers_error<'a>(
+    machine_data: &MachineDatabase,
+    identifiers: impl IntoIterator<Item = &'a str>,
+) -> LcError {
+    LcError::UnknownIdentifiers {
+        identifiers: identifiers
+            .into_iter()
+            .map(|identifier| (identifier.to_string(), suggest_identifiers(machine_data, identifier)))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catalog_numbers_accept_thousands_separators() {
+        assert_eq!(parse_catalog_number("1,200.5", DecimalSeparator::Dot), Some(1200.5));
+        assert_eq!(parse_catalog_number("-1,000,000", DecimalSeparator::Dot), Some(-1000000.0));
+        assert_eq!(parse_catalog_number("12 000", DecimalSeparator::Dot), Some(12000.0));
+        assert_eq!(parse_catalog_number("14400.00", DecimalSeparator::Dot), Some(14400.0));
+        assert_eq!(parse_catalog_number("1,20", DecimalSeparator::Dot), None);
+        assert_eq!(parse_catalog_number("12a", DecimalSeparator::Dot), None);
+        assert_eq!(parse_catalog_number("1.200,5", DecimalSeparator::Comma), Some(1200.5));
+    }
+
+    #[test]
+    fn catalog_format_applies_only_to_the_parsed_text() {
+        let format = CatalogFormat { delimiter: b';', decimal_separator: DecimalSeparator::Comma, ..CatalogFormat::default() };
+        let csv = "\
+model number;machine code;AHRI;Btu@95min;Btu@lowest max;lowest temperature;Btu@5max;Btu@17max;Btu@17rated;Btu@47max;Btu@95rated;HSPF;SEER;Price
+SEMI18;18S;1001;7.000;;;;16.400;;;;10;21;799,7
+";
+        let catalog = parse_machine_data(csv, "fixture", format, false, false).unwrap();
+        assert_eq!(catalog.get("SEMI18").unwrap().btu_17_max, Some(16400.0));
+        assert_eq!(catalog.get("SEMI18").unwrap().price, Some(799.7));
+        // 之后读取的内置目录不受影响
+        let embedded = parse_machine_data(CSV_DATA, "embedded catalog", CatalogFormat::default(), false, false).unwrap();
+        assert_eq!(embedded.get("KM18H5O").unwrap().btu_17_max, Some(16400.0));
+    }
+
+    #[test]
+    fn database_checks_models_and_codes_separately() {
+        let db = parse_machine_data(CSV_DATA, "embedded catalog", CatalogFormat::default(), false, false).unwrap();
+        assert!(db.contains_model("KM18H5O"));
+        assert!(!db.contains_code("KM18H5O"));
+        assert!(db.contains_code("18M"));
+        assert!(!db.contains_model("18M"));
+        assert!(!db.contains_model("KM18H5Ox"));
+    }
+
+    #[test]
+    fn display_name_prefers_a_shorter_machine_code() {
+        let machine = |model: &str, code: Option<&str>| MachineData {
+            model_number: model.to_string(),
+            machine_code: code.map(str::to_string),
+            ..Default::default()
+        };
+        assert_eq!(machine("KU36UHO", Some("36U")).display_name(), "36U");
+        assert_eq!(machine("KM18H5O", None).display_name(), "KM18H5O");
+        assert_eq!(machine("AB1", Some("LONGCODE")).display_name(), "AB1");
+        assert_eq!(machine("AB1", Some("XY1")).display_name(), "AB1");
+        assert_eq!(machine("KU36UHO", Some(" ")).display_name(), "KU36UHO");
+    }
+
+    #[test]
+    fn code_aliased_records_are_iterated_once() {
+        let db = parse_machine_data(CSV_DATA, "embedded catalog", CatalogFormat::default(), false, false).unwrap();
+        let models: Vec<&str> = db.models().map(|data| data.model_number.as_str()).collect();
+        assert_eq!(models.len(), db.len());
+        assert!(models.windows(2).all(|pair| pair[0] < pair[1]), "models are sorted and unique");
+        assert_eq!(models.iter().filter(|model| **model == "KM18H5O").count(), 1);
+        // 有机器代码的型号既能按代码找到，也只计一次
+        assert!(db.models().any(|data| data.machine_code.is_some()));
+        assert_eq!(db.identifiers().count(), db.len() + db.models().filter(|data| data.machine_code.is_some()).count());
+    }
+
+    #[test]
+    fn get_resolves_model_then_code_then_alias_then_ahri() {
+        let record = |model: &str, code: &str, ahri: u64| MachineData {
+            model_number: model.to_string(),
+            machine_code: Some(code.to_string()),
+            ahri: Some(ahri),
+            ..Default::default()
+        };
+        // BBB24 的机器代码与型号 AAA18 相同：型号优先
+        let mut db = MachineDatabase::from_iter([record("AAA18", "18A", 1001), record("BBB24", "AAA18", 1002)]);
+        assert_eq!(db.get("AAA18").unwrap().model_number, "AAA18");
+        assert_eq!(db.get("18A").unwrap().model_number, "AAA18");
+        assert!(db.insert_alias("upstairs", "18A"));
+        assert!(!db.insert_alias("attic", "NOPE"));
+        assert_eq!(db.get("upstairs").unwrap().model_number, "AAA18");
+        assert_eq!(db.get("1002").unwrap().model_number, "BBB24");
+        assert_eq!(db.by_ahri(1001).len(), 1);
+        assert!(db.by_ahri(9999).is_empty());
+        assert_eq!(db.len(), 2);
+
+        // 替换记录时旧的机器代码不再指向它
+        db.insert(record("AAA18", "18X", 1001));
+        assert!(db.get("18A").is_none());
+        assert_eq!(db.get("18X").unwrap().model_number, "AAA18");
+        assert_eq!(db.len(), 2);
+    }
+
+    #[test]
+    fn blank_model_number_falls_back_to_machine_code() {
+        let csv = "\
+model number,machine code,AHRI,Btu@95min,Btu@lowest max,lowest temperature,Btu@5max,Btu@17max,Btu@17rated,Btu@47max,Btu@95rated,HSPF,SEER,Price
+ ,18A,1001,6000,,,,15000,,,,,,
+,,1002,7000,,,,16000,,,,,,
+AAA24,24A,1003,8000,,,,21000,,,,,,
+";
+        let catalog = parse_machine_data(csv, "fixture", CatalogFormat::default(), false, false).unwrap();
+        assert_eq!(catalog.get("18A").unwrap().model_number, "18A");
+        assert!(catalog.get("").is_none() && catalog.get(" ").is_none());
+        assert_eq!(catalog.len(), 2);
+
+        let err = parse_machine_data(csv, "fixture", CatalogFormat::default(), false, true).unwrap_err();
+        assert!(matches!(err, LcError::DataLoad { line: Some(2), .. }), "{err:?}");
+    }
+
+    #[test]
+    fn unreadable_data_file_is_a_data_load_error() {
+        let err = load_machine_data(Some(Path::new("/nonexistent/catalog.csv")), CatalogFormat::default(), None, false, false).unwrap_err();
+        assert!(matches!(err, LcError::DataLoad { ref path, line: None, .. } if path == "/nonexistent/catalog.csv"));
+    }
+
+    #[test]
+    fn embedded_catalog_is_parsed_once() {
+        let first = embedded_database().unwrap();
+        let second = embedded_database().unwrap();
+        assert!(std::ptr::eq(first, second), "second access re-parsed the embedded catalog");
+        assert_eq!(load_machine_data(None, CatalogFormat::default(), None, false, false).unwrap().len(), first.len());
+    }
+
+    #[test]
+    fn reload_from_keeps_the_old_records_when_the_file_is_unreadable() {
+        let mut db = embedded_database().unwrap().clone();
+        let before = db.len();
+        assert!(db.reload_from(Path::new("/nonexistent/catalog.csv"), CatalogFormat::default(), None, false, false).is_err());
+        assert_eq!(db.len(), before);
+
+        let path = std::env::temp_dir().join(format!("lc-reload-{}.csv", std::process::id()));
+        let csv = "\
+model number,machine code,AHRI,Btu@95min,Btu@lowest max,lowest temperature,Btu@5max,Btu@17max,Btu@17rated,Btu@47max,Btu@95rated,HSPF,SEER,Price
+AAA18,18A,1001,6000,,,,15000,,,,,,
+";
+        std::fs::write(&path, csv).unwrap();
+        let reloaded = db.reload_from(&path, CatalogFormat::default(), None, false, false);
+        std::fs::remove_file(&path).unwrap();
+        reloaded.unwrap();
+        assert_eq!(db.len(), 1);
+        assert_eq!(db.get("18A").unwrap().model_number, "AAA18");
+    }
+
+    #[test]
+    fn catalog_template_example_row_fills_every_heating_field() {
+        let template = catalog_template();
+        let lines: Vec<&str> = template.lines().collect();
+        let (example_row, header) = (lines[1].strip_prefix("# ").unwrap(), lines[2]);
+        assert_eq!(header.split(',').collect::<Vec<_>>(), catalog_columns());
+        assert!(parse_machine_data(&template, "template", CatalogFormat::default(), false, false).is_err());
+
+        let uncommented = format!("{}\n{}\n", header, example_row);
+        let catalog = parse_machine_data(&uncommented, "template", CatalogFormat::default(), false, false).unwrap();
+        let example = catalog.get("KM18H5O").unwrap();
+        assert_eq!(example.heating_points(), vec![(-22.0, 10200.0), (5.0, 13600.0), (17.0, 16400.0), (47.0, 18100.0)]);
+        assert_eq!(example.cop_at_temp(17.0), Some(2.64));
+        assert_eq!(example.ahri, Some(211497152));
+    }
+
+    #[test]
+    fn range_cells_use_the_midpoint_by_default() {
+        assert_eq!(parse_range_value("18000-21000", CatalogFormat::default()), Some(19500.0));
+        assert_eq!(parse_range_value("21000 ~ 18000", CatalogFormat::default()), Some(19500.0));
+        assert_eq!(parse_range_value("n/a", CatalogFormat::default()), None);
+
+        let max_comma = CatalogFormat { range_basis: RangeBasis::Max, decimal_separator: DecimalSeparator::Comma, ..CatalogFormat::default() };
+        assert_eq!(parse_range_value("18.000-21.000", max_comma), Some(21000.0));
+    }
+
+    #[test]
+    fn lowest_point_at_a_rated_temperature_does_not_duplicate_it() {
+        // 最低温度正好是 5°F，但数值与 Btu@5 max 不同
+        let machine = MachineData {
+            model_number: "DUP18".to_string(),
+            btu_lowest_max: Some(11000.0),
+            lowest_temp: Some(5.0),
+            btu_5_max: Some(12000.0),
+            btu_17_max: Some(15000.0),
+            cop_lowest_max: Some(1.5),
+            cop_5_max: Some(1.8),
+            cop_17_max: Some(2.4),
+            ..Default::default()
+        };
+        assert_eq!(machine.heating_points(), vec![(5.0, 12000.0), (17.0, 15000.0)]);
+        assert_eq!(machine.conflicting_lowest_point(), Some((5.0, 11000.0, 12000.0)));
+        // 5°F 以下沿 5–17°F 的斜率外推，而不是取两个 5°F 点之间的零宽区间
+        assert_eq!(machine.calculate_heating_capacity_at_temp(-7.0), 9000.0);
+        assert_eq!(machine.calculate_heating_capacity_at_temp(5.0), 12000.0);
+        assert!((machine.cop_at_temp(-7.0).unwrap() - 1.2).abs() < 1e-9);
+
+        let agreeing = MachineData { btu_lowest_max: Some(12000.0), ..machine };
+        assert_eq!(agreeing.conflicting_lowest_point(), None);
+    }
+
+    #[test]
+    fn heating_points_are_sorted_and_fall_back_to_rated() {
+        let machine = MachineData {
+            btu_47_max: Some(20000.0),
+            btu_17_rated: Some(15000.0),
+            btu_5_max: Some(12000.0),
+            lowest_temp: Some(-13.0),
+            btu_lowest_max: Some(9000.0),
+            ..Default::default()
+        };
+        assert_eq!(
+            machine.heating_points(),
+            vec![(-13.0, 9000.0), (5.0, 12000.0), (17.0, 15000.0), (47.0, 20000.0)]
+        );
+        assert!(MachineData::default().heating_points().is_empty());
+    }
+
+    // 5°F 12000、17°F 15000、47°F 21000：两段斜率分别为 250 与 200 Btu/°F
+    fn three_point_machine() -> MachineData {
+        MachineData { btu_5_max: Some(12000.0), btu_17_max: Some(15000.0), btu_47_max: Some(21000.0), ..Default::default() }
+    }
+
+    #[test]
+    fn validation_finds_decreasing_and_missing_heating_points() {
+        assert_eq!(three_point_machine().decreasing_heating_segment(), None);
+        assert!(three_point_machine().missing_heating_temps().is_empty());
+
+        let machine = MachineData { btu_17_max: Some(16400.0), btu_47_rated: Some(7000.0), ..Default::default() };
+        assert_eq!(machine.decreasing_heating_segment(), Some(((17.0, 16400.0), (47.0, 7000.0))));
+        assert_eq!(machine.missing_heating_temps(), vec![5.0]);
+    }
+
+    #[test]
+    fn two_points_interpolate_linearly() {
+        let machine = MachineData { btu_17_max: Some(15000.0), btu_47_max: Some(21000.0), ..Default::default() };
+        assert_eq!(machine.calculate_heating_capacity_at_temp(27.0), 17000.0);
+        assert_eq!(machine.calculate_heating_capacity_at_temp(32.0), 18000.0);
+        let detail = machine.heating_capacity_detail(27.0, false).unwrap();
+        assert_eq!(detail.kind, InterpolationKind::Interpolated);
+        assert_eq!(detail.bracket, [(17.0, 15000.0), (47.0, 21000.0)]);
+    }
+
+    #[test]
+    fn target_on_a_knot_takes_the_catalog_value() {
+        let machine = three_point_machine();
+        let exact = machine.heating_capacity_detail(17.0, false).unwrap();
+        assert_eq!((exact.value, exact.kind), (15000.0, InterpolationKind::Exact));
+        assert_eq!(exact.bracket, [(17.0, 15000.0), (17.0, 15000.0)]);
+
+        // --force-interpolation 走插值路径，落在左侧区间的右端点上，数值不变
+        let forced = machine.heating_capacity_detail(17.0, true).unwrap();
+        assert_eq!((forced.value, forced.kind), (15000.0, InterpolationKind::Interpolated));
+        assert_eq!(forced.bracket, [(5.0, 12000.0), (17.0, 15000.0)]);
+        assert_eq!(machine.interpolate_heating_capacity_at_temp(11.0), 13500.0);
+        assert_eq!(machine.calculate_heating_capacity_at_temp(32.0), 18000.0);
+    }
+
+    #[test]
+    fn outside_the_range_extrapolates_along_the_nearest_segment() {
+        let machine = three_point_machine();
+        let below = machine.heating_capacity_detail(-1.0, false).unwrap();
+        assert_eq!((below.value, below.kind), (10500.0, InterpolationKind::Extrapolated));
+        assert_eq!(below.bracket, [(5.0, 12000.0), (17.0, 15000.0)]);
+
+        let above = machine.heating_capacity_detail(57.0, false).unwrap();
+        assert_eq!((above.value, above.kind), (23000.0, InterpolationKind::Extrapolated));
+        assert_eq!(above.bracket, [(17.0, 15000.0), (47.0, 21000.0)]);
+    }
+
+    #[test]
+    fn a_single_point_is_used_at_every_temperature() {
+        let machine = MachineData { btu_47_max: Some(20000.0), ..Default::default() };
+        for temp in [-22.0, 17.0, 47.0, 60.0] {
+            assert_eq!(machine.calculate_heating_capacity_at_temp(temp), 20000.0);
+        }
+        assert_eq!(machine.heating_capacity_detail(5.0, false).unwrap().kind, InterpolationKind::SinglePoint);
+    }
+
+    #[test]
+    fn a_record_without_heating_data_has_zero_capacity() {
+        let machine = MachineData::default();
+        assert_eq!(machine.calculate_heating_capacity_at_temp(17.0), 0.0);
+        assert_eq!(machine.interpolate_heating_capacity_at_temp(17.0), 0.0);
+        assert!(machine.heating_capacity_detail(17.0, false).is_none());
+        assert_eq!(machine.retention_at_temp(17.0), None);
+        assert_eq!(machine.cop_at_temp(17.0), None);
+    }
+
+    #[test]
+    fn retention_and_cop_follow_the_same_interpolation() {
+        let machine = MachineData { cop_5_max: Some(2.0), cop_47_max: Some(3.4), ..three_point_machine() };
+        assert_eq!(machine.retention_at_temp(5.0), Some(12000.0 / 21000.0));
+        assert!((machine.cop_at_temp(26.0).unwrap() - 2.7).abs() < 1e-9);
+    }
+
+    #[test]
+    fn eer_needs_capacity_and_power_and_a_plausible_ratio() {
+        let machine = MachineData { btu_95_rated: Some(17000.0), kw_95_rated: Some(1.41), ..Default::default() };
+        assert!((machine.eer_95_rated().unwrap() - 17000.0 / 1410.0).abs() < 1e-9);
+        assert_eq!(machine.cloned_with_override().kw_95_rated(None).build().eer_95_rated(), None);
+        assert_eq!(machine.cloned_with_override().btu_95_rated(Some(0.0)).build().eer_95_rated(), None);
+        // 功率栏误填了容量
+        let miscolumned = machine.cloned_with_override().kw_95_rated(Some(17000.0)).build();
+        assert_eq!(miscolumned.eer_95_rated(), None);
+        assert!((miscolumned.implausible_eer_95_rated().unwrap() - 0.001).abs() < 1e-9);
+        assert_eq!(machine.implausible_eer_95_rated(), None);
+    }
+
+    #[test]
+    fn schema_reports_the_first_row_that_breaks_it() {
+        let schema = CatalogSchema::from_toml(
+            "required = [\"model number\", \"btu@17 max\"]\noptional = [\"Price\"]\nnumeric = [\"Btu@17max\", \"Price\"]\n",
+        )
+        .unwrap();
+        assert_eq!(schema.optional, Some(vec!["Price".to_string()]));
+
+        let valid = "model number,Btu@17max,Price\nAAA18,\"15,000\",\nBBB24,18000-21000,999\n";
+        assert!(schema.validate(valid, "fixture", CatalogFormat::default()).is_ok());
+
+        let empty_cell = "model number,Btu@17max\nAAA18,15000\nBBB24,\n";
+        assert!(matches!(schema.validate(empty_cell, "fixture", CatalogFormat::default()), Err(LcError::DataLoad { line: Some(3), .. })));
+
+        let not_a_number = "model number,Btu@17max,Price\nAAA18,15000,n/a\n";
+        assert!(matches!(schema.validate(not_a_number, "fixture", CatalogFormat::default()), Err(LcError::DataLoad { line: Some(2), .. })));
+
+        let extra_column = "model number,Btu@17max,SEER\nAAA18,15000,21\n";
+        let err = schema.validate(extra_column, "fixture", CatalogFormat::default()).unwrap_err();
+        assert!(err.to_string().ends_with("column(s) not in the schema: SEER"), "{err}");
+        let without_optional = CatalogSchema { optional: None, ..schema.clone() };
+        assert!(without_optional.validate(extra_column, "fixture", CatalogFormat::default()).is_ok());
+
+        let missing_column = "model number,Price\nAAA18,900\n";
+        let err = schema.validate(missing_column, "fixture", CatalogFormat::default()).unwrap_err();
+        assert!(matches!(err, LcError::DataLoad { line: Some(1), .. }), "{err:?}");
+        assert!(err.to_string().ends_with("missing required column(s): btu@17 max"));
+    }
+
+    #[test]
+    fn error_lines_count_the_skipped_metadata_lines() {
+        let csv = "\
+# source: AHRI export
+# exported: 2024-05-01
+
+model number,machine code,AHRI,Btu@95min,Btu@lowest max,lowest temperature,Btu@5max,Btu@17max,Btu@17rated,Btu@47max,Btu@95rated,HSPF,SEER,Price
+AAA18,18A,1001,6000,,,,15000,,,,,,
+BBB24,24B
+";
+        let err = parse_machine_data(csv, "fixture", CatalogFormat::default(), false, false).unwrap_err();
+        assert!(matches!(err, LcError::DataLoad { line: Some(6), .. }), "{err:?}");
+
+        let schema = CatalogSchema::from_toml("numeric = [\"AHRI\"]").unwrap();
+        let bad_ahri = "# source\n# exported\n# by\nmodel number,AHRI\nAAA18,n/a\n";
+        let err = schema.validate(bad_ahri, "fixture", CatalogFormat::default()).unwrap_err();
+        assert!(matches!(err, LcError::DataLoad { line: Some(5), .. }), "{err:?}");
+    }
+
+    #[test]
+    fn schema_rejects_unknown_keys_and_non_string_columns() {
+        assert!(CatalogSchema::from_toml("requried = [\"AHRI\"]").is_err());
+        assert!(CatalogSchema::from_toml("required = \"AHRI\"").is_err());
+        assert!(CatalogSchema::from_toml("required = [1]").is_err());
+        assert_eq!(CatalogSchema::from_toml("").unwrap(), CatalogSchema::default());
+    }
+}
@@ -0,0 +1,69 @@
+//! 诊断警告的统一出口：所有写到 stderr 的 `warning:` 都经过 [`warn`]，由 `--log-format` 决定格式。
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// --log-format 的取值
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable `warning: ...` lines
+    #[default]
+    Text,
+    /// One JSON object per line, for log pipelines
+    Json,
+}
+
+/// 由 main 在解析参数后设置；未设置时按 Text 输出
+pub static LOG_FORMAT: std::sync::OnceLock<LogFormat> = std::sync::OnceLock::new();
+
+/// 一条诊断警告；`message` 是完整的人类可读文本，`kind`/`model` 供日志系统归类
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic<'a> {
+    pub level: &'static str,
+    pub kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<&'a str>,
+    pub message: String,
+}
+
+impl Diagnostic<'_> {
+    /// 按指定格式生成一行（不含换行符）
+    pub fn format(&self, format: LogFormat) -> String {
+        match format {
+            LogFormat::Text => format!("warning: {}", self.message),
+            LogFormat::Json => serde_json::to_string(self).expect("diagnostic serializes to JSON"),
+        }
+    }
+}
+
+/// 输出一条警告到 stderr
+pub fn warn(kind: &'static str, model: Option<&str>, message: impl Into<String>) {
+    let diagnostic = Diagnostic { level: "warn", kind, model, message: message.into() };
+    eprintln!("{}", diagnostic.format(LOG_FORMAT.get().copied().unwrap_or_default()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_and_json_formats() {
+        let diagnostic = Diagnostic {
+            level: "warn",
+            kind: "extrapolation",
+            model: Some("KM18H5O"),
+            message: "KM18H5O: capacity at -30°F is extrapolated".to_string(),
+        };
+        assert_eq!(diagnostic.format(LogFormat::Text), "warning: KM18H5O: capacity at -30°F is extrapolated");
+        let json: serde_json::Value = serde_json::from_str(&diagnostic.format(LogFormat::Json)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "level": "warn",
+                "kind": "extrapolation",
+                "model": "KM18H5O",
+                "message": "KM18H5O: capacity at -30°F is extrapolated",
+            })
+        );
+    }
+}
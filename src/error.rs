@@ -0,0 +1,53 @@
+//! 库函数返回的结构化错误：调用方（命令行、HTTP 服务）按变体区分处理，而不是比较错误文本
+
+use thiserror::Error;
+
+/// 解析机器输入、读取目录和计算时可能出现的错误
+#[derive(Debug, Error)]
+pub enum LcError {
+    /// 机器输入项的数量部分不是正整数，例如 `KM18H5Ox1.5`
+    #[error("Invalid quantity in {token}: {reason}")]
+    InvalidQuantity { token: String, reason: String },
+
+    /// 目录中没有这个型号或机器代码；`suggestions` 是按编辑距离排序的候选
+    #[error("Unknown model: {token}{}", did_you_mean(.suggestions))]
+    UnknownIdentifier { token: String, suggestions: Vec<String> },
+
+    /// --on-not-found error：输入中有不在目录里的项；每项是（标识，按编辑距离排序的候选）
+    #[error("Unknown models: {}", unknown_list(.identifiers))]
+    UnknownIdentifiers { identifiers: Vec<(String, Vec<String>)> },
+
+    /// 目录无法读取或解析；`path` 是文件路径或 "embedded catalog"，`line` 是出错的 CSV 行号
+    #[error("{path}{}: {source}", line.map(|l| format!(":{}", l)).unwrap_or_default())]
+    DataLoad {
+        source: Box<dyn std::error::Error + Send + Sync>,
+        path: String,
+        line: Option<u64>,
+    },
+
+    /// 输入中不同标识的个数超过 --max-models
+    #[error("Input has more than {limit} distinct models (raise --max-models if this is intended)")]
+    TooManyModels { limit: usize },
+
+    /// 设计温度不是有限的数（例如 `-t NaN`）
+    #[error("Invalid design temperature: {0}")]
+    InvalidDesignTemp(f64),
+
+    /// --aggregate-by code 时两个不同型号落到同一个键（共用机器代码，或代码与另一个型号的 model number 相同）
+    #[error("{key} would merge different models {first} and {second}; use --aggregate-by model")]
+    AmbiguousAggregationKey { key: String, first: String, second: String },
+}
+
+fn unknown_list(identifiers: &[(String, Vec<String>)]) -> String {
+    let items: Vec<String> =
+        identifiers.iter().map(|(token, suggestions)| format!("{}{}", token, did_you_mean(suggestions))).collect();
+    items.join(", ")
+}
+
+fn did_you_mean(suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        String::new()
+    } else {
+        format!(" (did you mean {}?)", suggestions.join(", "))
+    }
+}
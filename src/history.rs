@@ -0,0 +1,145 @@
+// --log-run 记录的运行历史（JSON Lines）以及 `lc history`
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use comfy_table::{Cell, CellAlignment};
+use load_calculation::calc::{CalculationResult, CalculationTotals, Recommendation};
+use load_calculation::data::{CSV_DATA, MachineDatabase};
+use load_calculation::diagnostics;
+use load_calculation::input::canonical_scenario;
+use load_calculation::render::{RenderOptions, TableOptions, create_styled_table, print_recommendation, print_summary_table};
+use serde::{Deserialize, Serialize};
+
+use crate::{Cli, HistoryAction};
+
+// --log-run 写入的一行记录
+#[derive(Debug, Serialize, Deserialize)]
+struct HistoryEntry {
+    timestamp: String,
+    args: Vec<String>,
+    machines: BTreeMap<String, u32>,
+    design_temp: f64,
+    totals: CalculationTotals,
+    recommendation: Recommendation,
+    data_hash: String,
+}
+
+fn history_path() -> Option<PathBuf> {
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))?;
+    Some(data_home.join("lc").join("history.jsonl"))
+}
+
+// FNV-1a，用来标记计算时使用的是哪一版目录数据
+fn catalog_hash(data_file: Option<&Path>) -> String {
+    let text = match data_file {
+        Some(path) => std::fs::read(path).unwrap_or_default(),
+        None => CSV_DATA.as_bytes().to_vec(),
+    };
+    let hash = text.iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+fn append_history(entry: &HistoryEntry) -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::Write;
+
+    let path = history_path().ok_or("cannot determine home directory")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+// 记录历史失败只警告，不影响计算结果
+pub(crate) fn log_run(cli: &Cli, result: &CalculationResult, user_input: &HashMap<String, u32>, machine_data: &MachineDatabase) {
+    let machines = match canonical_scenario(user_input, machine_data) {
+        Ok(machines) => machines.into_iter().collect(),
+        Err(e) => {
+            diagnostics::warn("history", None, format!("failed to write run history: {}", e));
+            return;
+        }
+    };
+    let entry = HistoryEntry {
+        timestamp: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        args: std::env::args().skip(1).collect(),
+        machines,
+        design_temp: cli.design_temp,
+        totals: result.totals.clone(),
+        recommendation: Recommendation::from_totals(&result.totals),
+        data_hash: catalog_hash(cli.data_file.as_deref()),
+    };
+    if let Err(e) = append_history(&entry) {
+        diagnostics::warn("history", None, format!("failed to write run history: {}", e));
+    }
+}
+
+// 无法解析的行（写到一半被中断、旧版本格式等）只警告并跳过，其余记录照常显示
+fn read_history() -> Result<Vec<HistoryEntry>, Box<dyn std::error::Error>> {
+    let path = history_path().ok_or("cannot determine home directory")?;
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("failed to read {}: {}", path.display(), e).into()),
+    };
+    let entries = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .filter_map(|(i, line)| match serde_json::from_str(line) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                diagnostics::warn("history", None, format!("skipping {} line {}: {}", path.display(), i + 1, e));
+                None
+            }
+        })
+        .collect();
+    Ok(entries)
+}
+
+pub(crate) fn print_history(
+    action: Option<&HistoryAction>,
+    limit: usize,
+    precision: usize,
+    table_options: TableOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let entries = read_history()?;
+
+    if let Some(HistoryAction::Show { number }) = action {
+        let entry = number
+            .checked_sub(1)
+            .and_then(|i| entries.get(i))
+            .ok_or_else(|| format!("No run #{} in history ({} recorded)", number, entries.len()))?;
+        println!(" Run #{} at {}: lc {}", number, entry.timestamp, entry.args.join(" "));
+        // 历史记录只保存了合计，按没有明细行的结果输出
+        let result = CalculationResult { rows: Vec::new(), totals: entry.totals.clone(), not_found: Vec::new() };
+        let options = RenderOptions { design_temp: entry.design_temp, precision, table_options, ..Default::default() };
+        print_summary_table(&result, &options);
+        print_recommendation(&result, precision);
+        return Ok(());
+    }
+
+    if entries.is_empty() {
+        println!(" No runs recorded yet (use --log-run)");
+        return Ok(());
+    }
+    let mut table = create_styled_table(table_options);
+    table.set_header(vec!["#", "Time", "Machines", "Design Temp", "Btu@design max"]);
+    let skip = entries.len().saturating_sub(limit);
+    for (i, entry) in entries.iter().enumerate().skip(skip) {
+        let machines: Vec<String> = entry.machines.iter().map(|(model, qty)| format!("{}x{}", model, qty)).collect();
+        table.add_row(vec![
+            Cell::new(i + 1),
+            Cell::new(&entry.timestamp),
+            Cell::new(machines.join(" ")),
+            Cell::new(entry.design_temp).set_alignment(CellAlignment::Right),
+            Cell::new(format!("{:.*}", precision, entry.totals.total_btu_design_max)).set_alignment(CellAlignment::Right),
+        ]);
+    }
+    println!("{table}");
+    Ok(())
+}
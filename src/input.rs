@@ -0,0 +1,347 @@
+//! 命令行机器输入的解析：型号 x 数量、机器代码 + 数量、--derate、--virtual 以及场景文件
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::data::{MachineData, MachineDatabase, unknown_identifier_error};
+use crate::error::LcError;
+
+/// 单个机器输入项：`KM18X6Ox2`（型号 x 数量）或 `18M1`（机器代码 + 数量）；没有数量时为 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MachineSpec {
+    pub identifier: String,
+    pub qty: u32,
+}
+
+impl std::str::FromStr for MachineSpec {
+    type Err = LcError;
+
+    fn from_str(item: &str) -> Result<Self, Self::Err> {
+        static MODEL_QTY_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(.+)x(\d+)$").unwrap());
+        static CODE_QTY_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^([a-zA-Z0-9]+?)(\d+)$").unwrap());
+
+        let (identifier, count_str) = if let Some(caps) = MODEL_QTY_RE.captures(item) {
+            (caps[1].to_string(), caps[2].to_string())
+        } else if CODE_QTY_RE.is_match(item) {
+            let last_char_index = item.rfind(|c: char| !c.is_ascii_digit());
+            if let Some(idx) = last_char_index {
+                 if idx < item.len() - 1 {
+                    let id = item[..idx+1].to_string();
+                    let qty = item[idx+1..].to_string();
+                    (id, qty)
+                } else {
+                    (item.to_string(), "1".to_string())
+                }
+            } else {
+                 return Err(LcError::InvalidQuantity {
+                     token: item.to_string(),
+                     reason: "no model or code before the quantity".to_string(),
+                 });
+            }
+        } else {
+             (item.to_string(), "1".to_string())
+        };
+
+        let qty: u32 = count_str.parse().map_err(|_| LcError::InvalidQuantity {
+            token: item.to_string(),
+            reason: "Qty must be integer".to_string(),
+        })?;
+        Ok(MachineSpec { identifier, qty })
+    }
+}
+
+/// --max-models 的默认值
+pub const DEFAULT_MAX_MODELS: usize = 1000;
+
+/// 解析命令行上的机器列表，相同标识的数量累加
+///
+/// 不同标识超过 `limit` 个（--max-models，默认 [`DEFAULT_MAX_MODELS`]）时报错，避免格式错误的输入文件生成巨大的表格
+pub fn parse_user_input(inputs: &[String], limit: usize) -> Result<HashMap<String, u32>, LcError> {
+    let mut input_map = HashMap::new();
+
+    for item in inputs {
+        let spec: MachineSpec = item.parse()?;
+        if input_map.len() >= limit && !input_map.contains_key(&spec.identifier) {
+            return Err(LcError::TooManyModels { limit });
+        }
+        let total: &mut u32 = input_map.entry(spec.identifier).or_insert(0);
+        *total = total.checked_add(spec.qty).ok_or_else(|| LcError::InvalidQuantity {
+            token: item.clone(),
+            reason: "total quantity is too large".to_string(),
+        })?;
+    }
+    Ok(input_map)
+}
+
+/// 解析 -t / --at-temp 等温度参数：必须是有限的数，NaN 或 inf 会得到无意义的容量和 COP
+pub fn parse_finite_temp(value: &str) -> Result<f64, String> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|t| t.is_finite())
+        .ok_or_else(|| format!("expected a finite temperature, got {:?}", value))
+}
+
+/// 解析 --derate "MODEL=FACTOR"，系数须在 (0, 2] 之间
+pub fn parse_derate(spec: &str) -> Result<(String, f64), String> {
+    let (model, factor) = spec
+        .split_once('=')
+        .ok_or_else(|| format!("Derate must look like MODEL=FACTOR: {}", spec))?;
+    let factor: f64 = factor.trim().parse().map_err(|_| format!("Invalid derate factor: {}", spec))?;
+    if !(factor > 0.0 && factor <= 2.0) {
+        return Err(format!("Derate factor must be between 0 and 2: {}", spec));
+    }
+    Ok((model.trim().to_string(), factor))
+}
+
+/// 把 --derate 的型号/代码统一成 model number
+pub fn resolve_derates(
+    machine_data: &MachineDatabase,
+    derates: &[(String, f64)],
+) -> Result<HashMap<String, f64>, LcError> {
+    derates
+        .iter()
+        .map(|(identifier, factor)| {
+            machine_data.get(identifier)
+                .map(|data| (data.model_number.clone(), *factor))
+                .ok_or_else(|| unknown_identifier_error(machine_data, identifier))
+        })
+        .collect()
+}
+
+/// 解析 --virtual "NAME:T=BTU,..."：5/17/47 对应各自的 max 点，其它温度作为最低温度点
+pub fn parse_virtual_machine(spec: &str) -> Result<MachineData, String> {
+    let (name, points) = spec
+        .split_once(':')
+        .ok_or_else(|| format!("Virtual model must look like NAME:T=BTU,...: {}", spec))?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(format!("Virtual model needs a name: {}", spec));
+    }
+
+    let mut machine = MachineData { model_number: name.to_string(), ..Default::default() };
+
+    for point in points.split(',') {
+        let (temp_str, btu_str) = point
+            .split_once('=')
+            .ok_or_else(|| format!("Point must look like T=BTU: {}", point))?;
+        let temp: f64 = temp_str.trim().parse().map_err(|_| format!("Invalid temperature: {}", temp_str))?;
+        let btu: f64 = btu_str.trim().parse().map_err(|_| format!("Invalid Btu value: {}", btu_str))?;
+
+        let slot = match temp {
+            5.0 => &mut machine.btu_5_max,
+            17.0 => &mut machine.btu_17_max,
+            47.0 => &mut machine.btu_47_max,
+            _ => {
+                if machine.lowest_temp.is_some() {
+                    return Err(format!("Only one point besides 5/17/47 is allowed: {}", spec));
+                }
+                machine.lowest_temp = Some(temp);
+                &mut machine.btu_lowest_max
+            }
+        };
+        *slot = Some(btu);
+    }
+    Ok(machine)
+}
+
+/// --ashrae 的冬季设计温度对（°F）：99% 与较暖的 97.5%；`city` 为内置表中的城市名
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AshraeDesign {
+    pub city: Option<&'static str>,
+    pub heating_99: f64,
+    pub heating_97_5: f64,
+}
+
+// 内置城市表（ASHRAE 冬季干球设计温度的近似值，°F）：(名称, 99%, 97.5%)
+const ASHRAE_CITIES: &[(&str, f64, f64)] = &[
+    ("Boston", 6.0, 9.0),
+    ("Chicago", -8.0, -4.0),
+    ("Denver", -5.0, 1.0),
+    ("Minneapolis", -16.0, -12.0),
+    ("New York", 11.0, 15.0),
+    ("Philadelphia", 10.0, 14.0),
+];
+
+/// 解析 --ashrae：内置城市名（忽略大小写、空格和连字符），或 "T99,T97.5" 两个温度
+pub fn parse_ashrae(spec: &str) -> Result<AshraeDesign, String> {
+    let normalize = |name: &str| name.chars().filter(char::is_ascii_alphanumeric).collect::<String>().to_lowercase();
+    if let Some(&(city, heating_99, heating_97_5)) = ASHRAE_CITIES.iter().find(|(city, ..)| normalize(city) == normalize(spec)) {
+        return Ok(AshraeDesign { city: Some(city), heating_99, heating_97_5 });
+    }
+    let Some((t99, t97_5)) = spec.split_once(',') else {
+        let cities: Vec<&str> = ASHRAE_CITIES.iter().map(|(city, ..)| *city).collect();
+        return Err(format!("expected \"T99,T97.5\" or one of: {}", cities.join(", ")));
+    };
+    let parse = |text: &str| text.trim().parse::<f64>().ok().filter(|t| t.is_finite());
+    let (Some(heating_99), Some(heating_97_5)) = (parse(t99), parse(t97_5)) else {
+        return Err(format!("invalid design temperatures: {}", spec));
+    };
+    if heating_97_5 < heating_99 {
+        return Err(format!("the 97.5% temperature must not be colder than the 99% one: {}", spec));
+    }
+    Ok(AshraeDesign { city: None, heating_99, heating_97_5 })
+}
+
+/// 场景文件：与命令行相同的机器写法，空白或换行分隔，# 之后为注释
+pub fn read_scenario_file(path: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("failed to read scenario {}: {}", path, e))?;
+    Ok(text
+        .lines()
+        .flat_map(|line| line.split('#').next().unwrap_or("").split_whitespace())
+        .map(str::to_string)
+        .collect())
+}
+
+/// 把机器代码等标识统一成型号再比较，未知标识保持原样
+///
+/// 合并后的数量超出 u32 时返回 [`LcError::InvalidQuantity`]
+pub fn canonical_scenario(
+    input: &HashMap<String, u32>,
+    machine_data: &MachineDatabase,
+) -> Result<Vec<(String, u32)>, LcError> {
+    let mut counts: HashMap<String, u32> = HashMap::new();
+    for (identifier, count) in input {
+        let key = machine_data.get(identifier).map(|d| d.model_number.clone()).unwrap_or_else(|| identifier.clone());
+        let total = counts.entry(key).or_insert(0);
+        *total = total.checked_add(*count).ok_or_else(|| LcError::InvalidQuantity {
+            token: identifier.clone(),
+            reason: "total quantity is too large".to_string(),
+        })?;
+    }
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort();
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn spec(token: &str) -> (String, u32) {
+        let spec: MachineSpec = token.parse().unwrap();
+        (spec.identifier, spec.qty)
+    }
+
+    #[test]
+    fn every_token_shape_parses() {
+        // 型号 x 数量；型号本身含 x 时取最后一个
+        assert_eq!(spec("KM18H5Ox2"), ("KM18H5O".to_string(), 2));
+        assert_eq!(spec("KM18x6Ox1"), ("KM18x6O".to_string(), 1));
+        // 机器代码 + 数量：末尾的连续数字是数量
+        assert_eq!(spec("18M1"), ("18M".to_string(), 1));
+        assert_eq!(spec("18M12"), ("18M".to_string(), 12));
+        // 不以数字结尾，或含有字母数字以外的字符：数量为 1
+        assert_eq!(spec("KW09HQ19SDO"), ("KW09HQ19SDO".to_string(), 1));
+        assert_eq!(spec("ABC-12"), ("ABC-12".to_string(), 1));
+        assert_eq!(spec("KM18H5Ox-1"), ("KM18H5Ox-1".to_string(), 1));
+    }
+
+    #[test]
+    fn repeated_identifiers_are_summed() {
+        let input = parse_user_input(&["KM18H5Ox2".to_string(), "KM18H5O".to_string(), "18M3".to_string()], DEFAULT_MAX_MODELS).unwrap();
+        assert_eq!(input.len(), 2);
+        assert_eq!(input["KM18H5O"], 3);
+        assert_eq!(input["18M"], 3);
+        assert!(parse_user_input(&[], DEFAULT_MAX_MODELS).unwrap().is_empty());
+    }
+
+    #[test]
+    fn digits_only_token_has_no_identifier() {
+        assert!(matches!("123".parse::<MachineSpec>(), Err(LcError::InvalidQuantity { ref token, .. }) if token == "123"));
+    }
+
+    #[test]
+    fn non_integer_quantity_is_an_invalid_quantity_error() {
+        let err = parse_user_input(&["KM18H5Ox2".to_string(), "KM18H5Ox99999999999".to_string()], DEFAULT_MAX_MODELS).unwrap_err();
+        assert!(matches!(err, LcError::InvalidQuantity { ref token, .. } if token == "KM18H5Ox99999999999"));
+    }
+
+    // 三种写法：型号x数量、机器代码+数量、不带数量的型号（数量为 1）
+    #[derive(Debug, Clone)]
+    enum Token {
+        ModelQty(String, u32),
+        CodeQty(String, u32),
+        Model(String),
+    }
+
+    impl Token {
+        fn render(&self) -> String {
+            match self {
+                Token::ModelQty(model, qty) => format!("{}x{}", model, qty),
+                Token::CodeQty(code, qty) => format!("{}{}", code, qty),
+                Token::Model(model) => model.clone(),
+            }
+        }
+
+        fn expected(&self) -> (&str, u32) {
+            match self {
+                Token::ModelQty(id, qty) | Token::CodeQty(id, qty) => (id, *qty),
+                Token::Model(id) => (id, 1),
+            }
+        }
+    }
+
+    fn token() -> impl Strategy<Value = Token> {
+        prop_oneof![
+            ("[A-Z][A-Za-z0-9/()-]{0,12}", any::<u32>()).prop_map(|(model, qty)| Token::ModelQty(model, qty)),
+            ("[0-9]{1,3}[A-Z]", any::<u32>()).prop_map(|(code, qty)| Token::CodeQty(code, qty)),
+            "[A-Z][A-Z0-9]{0,10}[A-Z]".prop_map(Token::Model),
+        ]
+    }
+
+    #[test]
+    fn ashrae_accepts_a_city_or_a_temperature_pair() {
+        let chicago = parse_ashrae("chicago").unwrap();
+        assert_eq!((chicago.city, chicago.heating_99, chicago.heating_97_5), (Some("Chicago"), -8.0, -4.0));
+        assert_eq!(parse_ashrae("new-york").unwrap().city, Some("New York"));
+        assert_eq!(parse_ashrae("-3, 2").unwrap(), AshraeDesign { city: None, heating_99: -3.0, heating_97_5: 2.0 });
+        assert!(parse_ashrae("Atlantis").is_err());
+        assert!(parse_ashrae("5,2").is_err());
+        assert!(parse_ashrae("5,warm").is_err());
+    }
+
+    #[test]
+    fn distinct_models_beyond_the_limit_are_rejected() {
+        let mut inputs: Vec<String> = (0..3).map(|i| format!("MODEL{i}X")).collect();
+        // 重复的标识不计入上限
+        inputs.push("MODEL0Xx2".to_string());
+        assert_eq!(parse_user_input(&inputs, 3).unwrap().len(), 3);
+        assert!(parse_user_input(&inputs, DEFAULT_MAX_MODELS).is_ok());
+        inputs.push("ONEMOREX".to_string());
+        let err = parse_user_input(&inputs, 3).unwrap_err();
+        assert!(matches!(err, LcError::TooManyModels { limit: 3 }));
+    }
+
+    #[test]
+    fn quantity_overflow_is_an_error_not_a_panic() {
+        let err = parse_user_input(&["KM18H5Ox4294967295".to_string(), "KM18H5O".to_string()], DEFAULT_MAX_MODELS).unwrap_err();
+        assert!(matches!(err, LcError::InvalidQuantity { ref token, .. } if token == "KM18H5O"));
+    }
+
+    proptest! {
+        #[test]
+        fn rendered_tokens_parse_back(tokens in prop::collection::vec(token(), 0..8)) {
+            let inputs: Vec<String> = tokens.iter().map(Token::render).collect();
+            let mut expected: HashMap<&str, u64> = HashMap::new();
+            for token in &tokens {
+                let (id, qty) = token.expected();
+                *expected.entry(id).or_insert(0) += u64::from(qty);
+            }
+            match parse_user_input(&inputs, DEFAULT_MAX_MODELS) {
+                Ok(parsed) => {
+                    let parsed: HashMap<&str, u64> = parsed.iter().map(|(id, qty)| (id.as_str(), u64::from(*qty))).collect();
+                    prop_assert_eq!(parsed, expected);
+                }
+                Err(e) => {
+                    prop_assert!(matches!(e, LcError::InvalidQuantity { .. }), "{:?}", e);
+                    prop_assert!(expected.values().any(|total| *total > u64::from(u32::MAX)));
+                }
+            }
+        }
+    }
+}
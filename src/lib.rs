@@ -0,0 +1,22 @@
+//! lc 的计算核心：读取设备目录、解析机器输入、按设计温度插值汇总，以及报告的输出。
+//!
+//! 命令行程序（`src/main.rs`）只负责参数解析和把各部分串起来；其他工具可以直接使用这些模块：
+//!
+//! ```
+//! use load_calculation::calc::{CalculationOptions, perform_calculation};
+//! use load_calculation::data::{CatalogFormat, load_machine_data};
+//! use load_calculation::input::{DEFAULT_MAX_MODELS, parse_user_input};
+//!
+//! let catalog = load_machine_data(None, CatalogFormat::default(), None, false, false).unwrap();
+//! let input = parse_user_input(&["KM18H5Ox2".to_string()], DEFAULT_MAX_MODELS).unwrap();
+//! let result = perform_calculation(&input, &catalog, &CalculationOptions::default()).unwrap();
+//! assert_eq!(result.totals.total_btu_design_max, 32800.0);
+//! ```
+
+pub mod calc;
+pub mod catalog;
+pub mod data;
+pub mod diagnostics;
+pub mod error;
+pub mod input;
+pub mod render;
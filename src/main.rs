@@ -1,154 +1,64 @@
-use clap::Parser;
-use std::collections::HashMap;
-use serde::Deserialize;
-use regex::Regex;
-use lazy_static::lazy_static;
-// 引入 ContentArrangement 用于自适应宽度，引入 Color 用于颜色
-use comfy_table::{Table, presets, Attribute, Cell, CellAlignment, Color, ContentArrangement};
-
-// --- 0. 嵌入数据 ---
-const CSV_DATA: &str = include_str!("../data/equipmentInfo.csv");
-
-// --- 1. 增强版反序列化助手 ---
-fn deserialize_f64_custom<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    let opt_s: Result<Option<String>, _> = Deserialize::deserialize(deserializer);
-    match opt_s {
-        Ok(Some(s)) => {
-            let trimmed = s.trim();
-            if trimmed.is_empty() { return Ok(None); }
-            match trimmed.parse::<f64>() {
-                Ok(v) => if v > -90000.0 { Ok(Some(v)) } else { Ok(None) },
-                Err(_) => Ok(None),
-            }
-        },
-        Ok(None) => Ok(None),
-        Err(_) => Ok(None),
-    }
-}
-
-fn deserialize_ahri<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    let opt_v: Result<Option<f64>, _> = Deserialize::deserialize(deserializer);
-    match opt_v {
-        Ok(Some(v)) => if v > 0.0 { Ok(Some(v as u64)) } else { Ok(None) },
-        _ => Ok(None),
-    }
-}
-
-// --- 2. 数据模型 ---
-#[derive(Debug, Deserialize, Clone)]
-pub struct MachineData {
-    #[serde(rename = "model number")]
-    pub model_number: String,
-    
-    #[serde(rename = "machine code")]
-    pub machine_code: Option<String>,
-    
-    #[serde(rename = "AHRI", deserialize_with = "deserialize_ahri")]
-    pub ahri: Option<u64>,
-
-    #[serde(rename = "Btu@95min", deserialize_with = "deserialize_f64_custom")]
-    pub btu_95_min: Option<f64>,
-
-    // Heating points for interpolation
-    #[serde(rename = "Btu@lowest max", deserialize_with = "deserialize_f64_custom")]
-    pub btu_lowest_max: Option<f64>,
-
-    #[serde(rename = "lowest temperature", deserialize_with = "deserialize_f64_custom")]
-    pub lowest_temp: Option<f64>,
-
-    #[serde(rename = "Btu@5max", deserialize_with = "deserialize_f64_custom")]
-    pub btu_5_max: Option<f64>,
-
-    #[serde(rename = "Btu@17max", deserialize_with = "deserialize_f64_custom")]
-    pub btu_17_max: Option<f64>,
-
-    #[serde(rename = "Btu@17rated", deserialize_with = "deserialize_f64_custom")]
-    pub btu_17_rated: Option<f64>,
-
-    #[serde(rename = "Btu@47max", deserialize_with = "deserialize_f64_custom")]
-    pub btu_47_max: Option<f64>,
-
-    // For Loan
-    #[serde(rename = "Btu@95rated", deserialize_with = "deserialize_f64_custom")]
-    pub btu_95_rated: Option<f64>,
+// lc 命令行：参数定义、配置文件合并，以及把各子命令接到 load_calculation 库上
+mod commands;
+mod config;
+mod history;
+#[cfg(feature = "server")]
+mod server;
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{ArgGroup, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use load_calculation::calc::{AggregateBy, CalculationOptions, RequirementCheck, SortBy, perform_calculation};
+use load_calculation::catalog::{
+    CurveFormat, DataField, StatsFormat, TopMetric, compare_machines, curve_compare, lookup_ahri, print_catalog_stats,
+    print_code_mapping, print_db_validation, print_interpolated_value, print_machine_info, print_missing_fields,
+    run_self_test, search_catalog, suggest_single_unit, top_models,
+};
+use load_calculation::data::{
+    CatalogFormat, CatalogSchema, DecimalSeparator, MachineData, MachineDatabase, RangeBasis, catalog_template,
+    check_csv_encoding, check_required_columns, load_machine_data, parse_csv_delimiter, unknown_identifiers_error,
+};
+use load_calculation::diagnostics::{self, LOG_FORMAT, LogFormat};
+use load_calculation::error::LcError;
+use load_calculation::input::{
+    AshraeDesign, DEFAULT_MAX_MODELS, parse_ashrae, parse_derate, parse_finite_temp, parse_user_input, parse_virtual_machine,
+    resolve_derates,
+};
+use load_calculation::render::{
+    DisplayBy, Locale, OutputFormat, RenderOptions, ReportFormat, SummaryOrder, TableOptions, TableStyle,
+    print_area_metrics, print_ashrae_totals, print_catalog_rows, print_code_compliance, print_env_report,
+    print_explanation, print_json_report, print_loan_metrics, print_missing_data_warnings, print_openmetrics,
+    print_plotly_json, print_recommendation, print_report_header, print_requirement_check, print_summary_table,
+    render_table, warn_capacity_anomalies, warn_extrapolation, warn_implausible_eer,
+};
+
+use crate::commands::{
+    QuoteOptions, check_load, check_project, diff_scenarios, print_quote, rename_check, run_batch, watch_dir,
+};
+use crate::config::{apply_config_file, insert_aliases, run_alias, run_config, write_man_pages};
+use crate::history::{log_run, print_history};
 
-    #[serde(rename = "HSPF", deserialize_with = "deserialize_f64_custom")]
-    pub hspf: Option<f64>,
-
-    #[serde(rename = "SEER", deserialize_with = "deserialize_f64_custom")]
-    pub seer: Option<f64>,
-}
-
-impl MachineData {
-    fn calculate_heating_capacity_at_temp(&self, target_temp: f64) -> f64 {
-        let mut points = Vec::new();
-        
-        if let (Some(temp), Some(val)) = (self.lowest_temp, self.btu_lowest_max) {
-             points.push((temp, val));
-        }
-        if let Some(val) = self.btu_5_max { points.push((5.0, val)); }
-        if let Some(val) = self.btu_17_max { points.push((17.0, val)); }
-        if let Some(val) = self.btu_47_max { points.push((47.0, val)); }
-
-        if points.is_empty() { return 0.0; }
-        if points.len() == 1 { return points[0].1; }
-
-        points.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
-
-        let (p1, p2) = if target_temp <= points[0].0 {
-            (points[0], points[1])
-        } else if target_temp >= points.last().unwrap().0 {
-            let len = points.len();
-            (points[len-2], points[len-1])
-        } else {
-            let mut found = (points[0], points[1]);
-            for window in points.windows(2) {
-                if target_temp >= window[0].0 && target_temp <= window[1].0 {
-                    found = (window[0], window[1]);
-                    break;
-                }
-            }
-            found
-        };
-
-        let (x1, y1) = p1;
-        let (x2, y2) = p2;
-        
-        if (x2 - x1).abs() < 1e-6 { return y1; }
-
-        let slope = (y2 - y1) / (x2 - x1);
-        y1 + (target_temp - x1) * slope
-    }
-}
-
-#[derive(Debug, Default)]
-struct CalculationTotals {
-    total_btu_95_min: f64,
-    total_btu_5_max: f64,
-    total_btu_17_max: f64,
-    total_btu_17_rated: f64,
-    total_btu_design_max: f64,
-
-    total_btu_95_rated: f64,
-    weighted_hspf_sum: f64,
-    weighted_seer_sum: f64,
-}
-
-// --- CLI Def ---
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None, name = "lc")]
+#[command(author, version, about, long_about = None, name = "lc", subcommand_negates_reqs = true)]
+#[command(group(ArgGroup::new("cop_column").args(["show_cop", "color_by_efficiency"]).multiple(true)))]
 pub struct Cli {
-    #[arg(required = true, help = "ModelNumberxQty or MachineCodeQty (e.g. KM18X6Ox1 or 18M1)")]
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[arg(required_unless_present_any = ["validate_db", "self_test"], help = "ModelNumberxQty or MachineCodeQty (e.g. KM18X6Ox1 or 18M1)")]
     pub machines: Vec<String>,
 
     /// Design temperature for heating calculation
-    #[arg(short = 't', long, default_value_t = 17.0, env = "LC_DESIGN_TEMP")]
+    #[arg(
+        short = 't',
+        long,
+        default_value_t = 17.0,
+        env = "LC_DESIGN_TEMP",
+        global = true,
+        allow_negative_numbers = true,
+        value_parser = parse_finite_temp
+    )]
     pub design_temp: f64,
 
     /// Floor area in square feet (optional). If provided, calculates BHL/SF and BH/SF.
@@ -158,332 +68,989 @@ pub struct Cli {
     /// Calculate Loan/Rebate metrics (Avg HSPF, Avg SEER, etc.)
     #[arg(short = 'l', long)]
     pub loan: bool,
+
+    /// Load the equipment catalog from this CSV file instead of the embedded data
+    #[arg(long, visible_alias = "data", alias = "db", env = "LC_DATA_FILE", global = true)]
+    pub data_file: Option<PathBuf>,
+
+    /// TOML column schema (`required`, `optional`, `numeric` column lists) checked row by row
+    /// when loading --data-file (the embedded catalog is not checked). When `optional` is given,
+    /// columns listed in neither `required` nor `optional` are rejected
+    #[arg(long, value_name = "PATH", global = true)]
+    pub schema: Option<PathBuf>,
+
+    /// Verify the --data-file is valid UTF-8 and report the offset of the first bad byte
+    #[arg(long, global = true)]
+    pub check_csv_encoding: bool,
+
+    /// Fail unless the catalog header has each of these columns, e.g. "AHRI,Btu@17max"
+    /// (compared ignoring case and whitespace)
+    #[arg(long, value_name = "COLUMNS", value_delimiter = ',', global = true)]
+    pub require_columns: Vec<String>,
+
+    /// Field delimiter of the catalog CSV (a single character, or "tab")
+    #[arg(long, value_name = "CHAR", default_value = ",", value_parser = parse_csv_delimiter, global = true)]
+    pub csv_delimiter: u8,
+
+    /// Decimal separator of numbers in the catalog; the other symbol is accepted as a thousands separator
+    #[arg(long, value_enum, default_value_t = DecimalSeparator::Dot, global = true)]
+    pub decimal_separator: DecimalSeparator,
+
+    /// Value used for catalog cells written as a range such as "18000-21000"
+    #[arg(long, value_enum, default_value_t = RangeBasis::Mid, global = true)]
+    pub range_basis: RangeBasis,
+
+    /// Drop placeholder records (no Btu data at all) while loading the catalog
+    #[arg(long, global = true)]
+    pub skip_placeholders: bool,
+
+    /// Reject catalog rows with a blank model number instead of keying them by machine code
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Check the loaded catalog and report placeholder records instead of calculating
+    #[arg(long)]
+    pub validate_db: bool,
+
+    /// Define a pseudo-model from heating points, e.g. "VM1:5=10000,17=15000,47=20000" (repeatable)
+    #[arg(long = "virtual", value_name = "SPEC", value_parser = parse_virtual_machine)]
+    pub virtual_machines: Vec<MachineData>,
+
+    /// Capacity factor for one model, e.g. "KM18H5O=0.95" (repeatable; unlisted models use 1.0)
+    #[arg(long = "derate", value_name = "MODEL=FACTOR", value_parser = parse_derate, global = true)]
+    pub derates: Vec<(String, f64)>,
+
+    /// Key used to merge identical machines in the detail table
+    #[arg(long, value_enum, default_value_t = AggregateBy::Model)]
+    pub aggregate_by: AggregateBy,
+
+    /// Row order of the detail table
+    #[arg(long, value_enum, default_value_t = SortBy::Model)]
+    pub sort: SortBy,
+
+    /// Group detail rows by the first N characters of the model number, with a subtotal per group
+    #[arg(long, value_name = "PREFIX_LEN", value_parser = clap::value_parser!(u16).range(1..))]
+    pub group_by_manufacturer: Option<u16>,
+
+    /// Identifier shown in the Model column (defaults to the machine code when it is shorter than the model number)
+    #[arg(long, value_enum)]
+    pub display_by: Option<DisplayBy>,
+
+    /// Language of the detail table column headers
+    #[arg(long, value_enum, default_value_t = Locale::En)]
+    pub locale: Locale,
+
+    /// Row order of the summary table
+    #[arg(long, value_enum, default_value_t = SummaryOrder::Fixed)]
+    pub summary_order: SummaryOrder,
+
+    /// Leave out summary rows whose total is zero
+    #[arg(long)]
+    pub hide_zero_totals: bool,
+
+    /// Always interpolate between points, even when the design temperature is an exact data point
+    #[arg(long, global = true)]
+    pub force_interpolation: bool,
+
+    /// Append this run to the history log (~/.local/share/lc/history.jsonl)
+    #[arg(long, env = "LC_LOG_RUN")]
+    pub log_run: bool,
+
+    /// Table border style [default: utf8]; use ascii or minimal on terminals without UTF-8 box drawing.
+    /// Without it the detail table switches to a compact layout when the terminal is too narrow
+    #[arg(long, value_enum, global = true)]
+    pub format_table: Option<TableStyle>,
+
+    /// Disable colors and other ANSI styling (same as setting NO_COLOR)
+    #[arg(long, global = true)]
+    pub no_color: bool,
+
+    /// Format of diagnostic warnings on stderr
+    #[arg(long, value_enum, default_value_t = LogFormat::Text, global = true)]
+    pub log_format: LogFormat,
+
+    /// Decimal places for displayed capacities, totals and the recommendation range
+    #[arg(long, default_value_t = 0, global = true)]
+    pub precision: usize,
+
+    /// Required heating load at the design temperature; reports whether the selection meets it
+    #[arg(long)]
+    pub required: Option<f64>,
+
+    /// Temperature (°F) at which local code requires the system to cover 100% of --code-load
+    #[arg(long, value_name = "F", requires = "code_load", allow_negative_numbers = true, value_parser = parse_finite_temp)]
+    pub code_temp: Option<f64>,
+
+    /// Heating load required by code at --code-temp; prints a COMPLIES / DOES NOT COMPLY verdict
+    #[arg(long, value_name = "BTU", requires = "code_temp")]
+    pub code_load: Option<f64>,
+
+    /// Also total the design max at the ASHRAE 99% and 97.5% winter temperatures:
+    /// a built-in city (e.g. "chicago") or "T99,T97.5"
+    #[arg(long, value_name = "CITY_OR_TEMPS", value_parser = parse_ashrae, allow_hyphen_values = true)]
+    pub ashrae: Option<AshraeDesign>,
+
+    /// Output format of the load calculation report
+    #[arg(long, alias = "format", value_enum, default_value_t = ReportFormat::Table)]
+    pub output: ReportFormat,
+
+    /// Print only the identifiers not found in the catalog, one per line (exits with status 1 when any)
+    #[arg(long, conflicts_with_all = ["output", "emit_shell_vars"])]
+    pub list_unmatched: bool,
+
+    /// Print the totals as one line of shell assignments for `eval $(lc --emit-shell-vars ...)`
+    #[arg(long, conflicts_with = "output")]
+    pub emit_shell_vars: bool,
+
+    /// Verify that interpolation reproduces every stored heating point of the catalog
+    #[arg(long, hide = true)]
+    pub self_test: bool,
+
+    /// Label used in place of "Btu" in the detail table headers (e.g. BTUH, Btu/h)
+    #[arg(long, default_value = "Btu")]
+    pub capacity_unit_label: String,
+
+    /// Add a COP column to the detail table (at --at-temp, or the design temperature)
+    #[arg(long)]
+    pub show_cop: bool,
+
+    /// Color the COP column from red (lowest COP in the list) to green (highest); implies --show-cop
+    #[arg(long)]
+    pub color_by_efficiency: bool,
+
+    /// Add a "Cap. Ratio" column: capacity at the design temperature ÷ Btu@47 max, per unit
+    #[arg(long)]
+    pub show_capacity_ratio: bool,
+
+    /// Add columns with every catalog heating point (lowest, 5, 17 and 47°F max) for each row
+    #[arg(long)]
+    pub show_all_points: bool,
+
+    /// Show as much per machine as possible: --show-all-points, --include-model-code and --show-capacity-ratio
+    #[arg(long)]
+    pub wide: bool,
+
+    /// Temperature used for the COP column
+    #[arg(long, requires = "cop_column", allow_negative_numbers = true, value_parser = parse_finite_temp)]
+    pub at_temp: Option<f64>,
+
+    /// Add a cooling EER column (Btu@95 rated ÷ rated input power) and a capacity-weighted project EER
+    #[arg(long)]
+    pub show_eer: bool,
+
+    /// Add a "Cert. Date" column from the catalog's AHRI Cert Date column
+    #[arg(long)]
+    pub include_ahri_date: bool,
+
+    /// Leave out the AHRI# column when none of the listed models has an AHRI number
+    #[arg(long)]
+    pub quiet_ahri: bool,
+
+    /// Add a Code column with each model's machine code to the detail table
+    #[arg(long)]
+    pub include_model_code: bool,
+
+    /// Add per-machine and system seasonal COP estimates (AHRI Region IV bin hours) to the summary
+    #[arg(long)]
+    pub include_seasonal_cop: bool,
+
+    /// Add a `display` object with totals rounded to --precision to json/yaml output
+    #[arg(long)]
+    pub json_display: bool,
+
+    /// Omit the version / generated-at header line of the table report
+    #[arg(long)]
+    pub no_header: bool,
+
+    /// After the report, show how each number was derived (interpolation, derates, totals, recommendation)
+    #[arg(long, conflicts_with = "summary_only")]
+    pub explain: bool,
+
+    /// Print only the summary table and the recommendation line
+    #[arg(long)]
+    pub summary_only: bool,
+
+    /// Warn when capacity decreases with rising temperature around the design temperature
+    #[arg(long)]
+    pub warn_anomalies: bool,
+
+    /// Omit NOT FOUND rows from the detail table
+    #[arg(long)]
+    pub quiet_not_found: bool,
+
+    /// Fail when the input lists more than N distinct models (guards against malformed input files)
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_MAX_MODELS, global = true)]
+    pub max_models: usize,
+
+    /// What to do with identifiers that are not in the catalog
+    #[arg(long, value_enum, default_value_t = NotFoundAction::Warn)]
+    pub on_not_found: NotFoundAction,
+
+    /// List machines without catalog data covering the design temperature
+    #[arg(long)]
+    pub show_missing_data_warning: bool,
 }
 
-fn parse_user_input(inputs: &[String]) -> Result<HashMap<String, u32>, String> {
-    lazy_static! {
-        static ref MODEL_QTY_RE: Regex = Regex::new(r"^(.+)x(\d+)$").unwrap();
-        static ref CODE_QTY_RE: Regex = Regex::new(r"^([a-zA-Z0-9]+?)(\d+)$").unwrap();
-    }
-    
-    let mut input_map = HashMap::new();
-
-    for item in inputs {
-        let (identifier, count_str) = if let Some(caps) = MODEL_QTY_RE.captures(item) {
-            (caps[1].to_string(), caps[2].to_string())
-        } else if CODE_QTY_RE.is_match(item) {
-            let last_char_index = item.rfind(|c: char| !c.is_ascii_digit());
-            if let Some(idx) = last_char_index {
-                 if idx < item.len() - 1 {
-                    let id = item[..idx+1].to_string();
-                    let qty = item[idx+1..].to_string();
-                    (id, qty)
-                } else {
-                    (item.clone(), "1".to_string())
-                }
-            } else {
-                 return Err(format!("Format error: {}", item));
-            }
-        } else {
-             (item.clone(), "1".to_string())
-        };
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// List every model in the catalog
+    List {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+    /// Search model numbers, machine codes and AHRI numbers
+    Search {
+        /// Case-insensitive substring (or regex with --regex); omit to list every model matching the filters
+        pattern: Option<String>,
+
+        /// Treat the pattern as a regular expression
+        #[arg(long)]
+        regex: bool,
+
+        /// Only models with heating data at or below this temperature (°F), e.g. -13 for cold-climate units
+        #[arg(long, value_name = "F", allow_negative_numbers = true)]
+        min_temp_rating: Option<f64>,
+
+        /// Maximum number of rows to print
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+    /// Show every field of one model plus derived heating values
+    Info {
+        /// Model number, machine code or AHRI number
+        identifier: String,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+    /// Print one model's max heating capacity at a temperature
+    Interp {
+        /// Model number, machine code or AHRI number
+        identifier: String,
+
+        /// Outdoor temperature (°F)
+        #[arg(allow_negative_numbers = true, value_parser = parse_finite_temp)]
+        temp: f64,
+
+        /// Output format (json adds the interpolation details)
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+    /// Overlay 2-5 models' max heating capacity curves as an ASCII chart
+    CurveCompare {
+        /// Model numbers, machine codes or AHRI numbers
+        #[arg(num_args = 2..=5, required = true)]
+        models: Vec<String>,
+
+        /// Lowest temperature of the range (°F)
+        #[arg(long, default_value_t = -13.0, allow_negative_numbers = true, value_parser = parse_finite_temp)]
+        from: f64,
+
+        /// Highest temperature of the range (°F)
+        #[arg(long, default_value_t = 47.0, allow_negative_numbers = true, value_parser = parse_finite_temp)]
+        to: f64,
+
+        /// Temperature step of the csv rows (°F)
+        #[arg(long, default_value_t = 5.0, value_parser = parse_finite_temp)]
+        step: f64,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = CurveFormat::Chart)]
+        format: CurveFormat,
+    },
+    /// Find the model(s) certified under an AHRI number
+    Ahri {
+        /// AHRI certificate number
+        number: u64,
+
+        /// Output format (json is always an array)
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+    /// Suggest single units whose capacity at the design temperature covers a load
+    Suggest {
+        /// Heating load to cover (Btu/h)
+        #[arg(long)]
+        load: f64,
+
+        /// Largest acceptable capacity as a percentage of the load
+        #[arg(long, default_value_t = 140.0)]
+        max_oversize: f64,
+
+        /// Preferred capacity as a percentage of the load; results are sorted by closeness to it
+        #[arg(long, default_value_t = 115.0)]
+        target_oversize: f64,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+    /// Rank catalog models by a capacity metric
+    Top {
+        /// Ranking metric
+        #[arg(long, value_enum, default_value_t = TopMetric::Btu17Max)]
+        by: TopMetric,
+
+        /// Only include models whose Btu@95 min is at or below this value
+        #[arg(long)]
+        max_min_capacity: Option<f64>,
+
+        /// Number of models to show
+        #[arg(short = 'n', long, default_value_t = 10)]
+        limit: usize,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+    /// Summarize the catalog: counts by series, Btu@47 max spread and a capacity histogram
+    Stats {
+        /// Number of leading model-number characters that identify a brand/series
+        #[arg(long, default_value_t = 2, value_parser = clap::value_parser!(u16).range(1..))]
+        prefix_len: u16,
+
+        /// Histogram bucket size (Btu)
+        #[arg(long, default_value_t = 6000.0)]
+        bucket: f64,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = StatsFormat::Table)]
+        format: StatsFormat,
+    },
+    /// List models with missing catalog fields (exits with status 1 when any are missing)
+    Missing {
+        /// Only list models missing this field
+        #[arg(long, value_enum)]
+        only: Option<DataField>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+    /// Print the machine code to model number mapping
+    Codes {
+        /// Only show entries whose code or model contains this text (case-insensitive)
+        filter: Option<String>,
+
+        /// Show model number -> code instead, sorted by model
+        #[arg(long)]
+        reverse: bool,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+    /// Compare two models side by side
+    Compare {
+        /// First model number, machine code or AHRI number
+        first: String,
+
+        /// Second model number, machine code or AHRI number
+        second: String,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+    /// Check whether a machine list covers a heating load at -t (exits with status 1 on FAIL)
+    CheckLoad {
+        /// Heating load to cover at the design temperature (Btu/h)
+        #[arg(long)]
+        load: f64,
+
+        /// Smallest acceptable capacity as a percentage of the load
+        #[arg(long, default_value_t = 100.0)]
+        min_coverage: f64,
+
+        /// Largest acceptable capacity as a percentage of the load
+        #[arg(long)]
+        max_coverage: Option<f64>,
+
+        /// Machines to check (same syntax as the main command)
+        #[arg(required = true)]
+        machines: Vec<String>,
+    },
+    /// Print a customer-facing quote: equipment lines with prices, subtotal and tax
+    Quote {
+        /// Machines to quote (same syntax as the main command)
+        #[arg(required = true)]
+        machines: Vec<String>,
+
+        /// Sales tax rate in percent, added below the subtotal
+        #[arg(long)]
+        tax_rate: Option<f64>,
+
+        /// Quote number printed in the header
+        #[arg(long)]
+        quote_number: Option<String>,
+
+        /// Title printed in the header
+        #[arg(long, default_value = "Heat Pump Equipment Quote")]
+        title: String,
+
+        /// List models without a catalog price as TBD instead of failing
+        #[arg(long)]
+        allow_missing_prices: bool,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = QuoteFormat::Table)]
+        format: QuoteFormat,
+    },
+    /// Run the calculation for every scenario file in a directory or glob
+    ///
+    /// Prints one JSON line per file unless --output-dir is given. Exits with
+    /// status 1 when any file failed.
+    Batch {
+        /// Directory of scenario files, or a glob such as 'jobs/*.txt'
+        source: String,
+
+        /// Write one report per input file into this directory instead of JSONL on stdout
+        #[arg(long)]
+        output_dir: Option<PathBuf>,
+
+        /// Format of the files written to --output-dir
+        #[arg(long, value_enum, default_value_t = BatchFormat::Json)]
+        format: BatchFormat,
+
+        /// Treat unknown models as a failure of that file
+        #[arg(long)]
+        strict: bool,
+
+        /// Print a table of every job's totals at the end
+        #[arg(long)]
+        summary: bool,
+    },
+    /// Watch a drop folder and write a sibling .result.json for every new or changed scenario
+    ///
+    /// Scenario files are `.txt` (same syntax as the command line) or `.toml`
+    /// (`machines = [...]`, optional `design_temp` and `required`). Files that
+    /// fail get a sibling `.error.txt` instead.
+    WatchDir {
+        /// Directory to watch
+        dir: PathBuf,
+
+        /// Format of the result files
+        #[arg(long, value_enum, default_value_t = BatchFormat::Json)]
+        format: BatchFormat,
+
+        /// Polling interval in milliseconds; a file is processed once it is unchanged for one interval
+        #[arg(long, default_value_t = 1000)]
+        interval_ms: u64,
+
+        /// Process the files that are out of date and exit instead of watching
+        #[arg(long)]
+        once: bool,
+    },
+    /// Re-validate a saved machine list against the current catalog (--data-file)
+    ///
+    /// Reports quoted models that are no longer in the catalog or whose capacity
+    /// points changed since the baseline catalog. Exits with status 1 when any are found.
+    CheckProject {
+        /// Saved machine list (same syntax as the command line, # starts a comment)
+        file: String,
+
+        /// Catalog the project was quoted against (defaults to the embedded catalog)
+        #[arg(long)]
+        baseline: Option<PathBuf>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+    /// Report scenario-file identifiers that no longer resolve or whose model was superseded
+    ///
+    /// Replacements come from the catalog's `superseded_by` column. Exits with
+    /// status 1 when unresolved identifiers remain.
+    RenameCheck {
+        /// Scenario files to check
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+
+        /// Rewrite superseded identifiers in place (the original is kept as <file>.bak, or <file>.bak.N if that exists)
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Print a blank catalog for --data-file: the expected header row, preceded by a commented-out example row
+    Template {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = TemplateFormat::Csv)]
+        format: TemplateFormat,
+    },
+    /// Write roff man pages for lc and every subcommand
+    #[command(hide = true)]
+    Man {
+        /// Write lc.1, lc-<subcommand>.1 into this directory instead of stdout
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+    },
+    /// Serve the catalog and calculation over a local HTTP API
+    #[cfg(feature = "server")]
+    Serve {
+        /// Port to listen on (127.0.0.1)
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+    },
+    /// Manage model aliases stored in the [aliases] table of the config file
+    Alias {
+        #[command(subcommand)]
+        action: AliasAction,
+    },
+    /// Show the effective configuration, or get/set/unset a default in the config file
+    Config {
+        #[command(subcommand)]
+        action: Option<ConfigAction>,
+    },
+    /// List runs recorded with --log-run
+    History {
+        #[command(subcommand)]
+        action: Option<HistoryAction>,
+
+        /// Number of most recent runs to list
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Diff two machine lists: `lc diff A.txt B.txt` or `lc diff KM18H5O2 -- KM18H5O3`
+    ///
+    /// Exits with status 1 when the scenarios differ.
+    Diff {
+        /// Two scenario files, or scenario A's machines when `--` is used
+        #[arg(required = true)]
+        first: Vec<String>,
+
+        /// Scenario B's machines (after `--`)
+        #[arg(last = true)]
+        second: Vec<String>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+}
 
-        let count: u32 = count_str.parse().map_err(|_| "Qty must be integer")?;
-        *input_map.entry(identifier).or_insert(0) += count;
-    }
-    Ok(input_map)
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NotFoundAction {
+    /// Keep NOT FOUND rows in the report and list them on stderr
+    Warn,
+    /// Fail with status 1 before printing the report
+    Error,
+    /// Leave them out of the report without a message
+    Skip,
 }
 
-fn load_machine_data() -> Result<HashMap<String, MachineData>, Box<dyn std::error::Error>> {
-    let mut reader = csv::Reader::from_reader(CSV_DATA.as_bytes());
-    let mut data_map = HashMap::new();
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    /// Print the value stored in the config file
+    Get { key: String },
+    /// Validate and store a value in the config file
+    Set {
+        key: String,
+        #[arg(allow_hyphen_values = true)]
+        value: String,
+    },
+    /// Remove a value from the config file
+    Unset { key: String },
+}
 
-    for result in reader.deserialize() {
-        let record: MachineData = result.map_err(|e| format!("CSV Parse Error: {}", e))?;
-        data_map.insert(record.model_number.clone(), record.clone());
-        if let Some(code) = &record.machine_code {
-            data_map.insert(code.clone(), record);
-        }
-    }
-    Ok(data_map)
+#[derive(Subcommand, Debug)]
+pub enum AliasAction {
+    /// Add an alias for a model number or machine code
+    Add { name: String, target: String },
+    /// Remove an alias
+    Rm { name: String },
+    /// List aliases with their target and its Btu@47 max
+    List,
 }
 
-// 辅助函数：统一创建表格样式 (更清爽的水平线风格)
-fn create_styled_table() -> Table {
-    let mut table = Table::new();
-    table.load_preset(presets::UTF8_FULL_CONDENSED);
-    table.set_content_arrangement(ContentArrangement::Dynamic);
-    table
+#[derive(Subcommand, Debug)]
+pub enum HistoryAction {
+    /// Re-print the summary of run N (as numbered by `lc history`)
+    Show {
+        number: usize,
+    },
 }
 
-// 辅助函数：打印带颜色的分节标题
-fn print_section_title(title: &str, color: Color) {
-    println!();
-    let mut table = Table::new();
-    table.load_preset(presets::NOTHING);
-    table.add_row(vec![
-        Cell::new(format!("=== {} ===", title)).add_attribute(Attribute::Bold).fg(color)
-    ]);
-    println!("{table}");
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TemplateFormat {
+    Csv,
 }
 
-fn perform_calculation(
-    user_input: &HashMap<String, u32>,
-    machine_data: &HashMap<String, MachineData>,
-    design_temp: f64,
-) -> CalculationTotals {
-    print_section_title("LOAD CALCULATION", Color::Blue);
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BatchFormat {
+    Json,
+    Yaml,
+}
 
-    let mut totals = CalculationTotals::default();
-    let mut table = create_styled_table();
-    
-    let header_design_label = format!("Btu@{} max", design_temp);
-    
-    table.set_header(vec![
-        Cell::new("Model"),
-        Cell::new("Qty"),
-        Cell::new("AHRI#"),
-        Cell::new("Btu@95 min"),
-        Cell::new(&header_design_label),
-    ]);
-
-    let mut canonical_counts: HashMap<String, u32> = HashMap::new();
-    let mut not_found_inputs: Vec<(&String, &u32)> = Vec::new();
-
-    for (identifier, count) in user_input {
-        if let Some(data) = machine_data.get(identifier) {
-            *canonical_counts.entry(data.model_number.clone()).or_insert(0) += count;
-        } else {
-            not_found_inputs.push((identifier, count));
-        }
+impl Cli {
+    // 命令行参数到计算参数的唯一转换点；--derate 的型号/代码在这里统一成 model number
+    pub(crate) fn calculation_options(&self, machine_data: &MachineDatabase) -> Result<CalculationOptions, LcError> {
+        Ok(CalculationOptions::default()
+            .design_temp(self.design_temp)
+            .aggregate_by(self.aggregate_by)
+            .sort_by(self.sort)
+            .force_interpolation(self.force_interpolation)
+            .derates(resolve_derates(machine_data, &self.derates)?))
     }
 
-    let mut sorted_models: Vec<_> = canonical_counts.into_iter().collect();
-    sorted_models.sort_by(|a, b| a.0.cmp(&b.0));
-
-    for (model_number, count) in sorted_models {
-        if let Some(data) = machine_data.get(&model_number) {
-            let qty = count as f64;
-            
-            let ahri = data.ahri.map(|v| v.to_string()).unwrap_or("-".to_string());
-            let btu_95_min = data.btu_95_min.unwrap_or(0.0);
-            let btu_design_max = data.calculate_heating_capacity_at_temp(design_temp);
-
-            totals.total_btu_95_min += btu_95_min * qty;
-            totals.total_btu_design_max += btu_design_max * qty;
-            
-            totals.total_btu_5_max += data.btu_5_max.unwrap_or(0.0) * qty;
-            totals.total_btu_17_max += data.btu_17_max.unwrap_or(0.0) * qty;
-            totals.total_btu_17_rated += data.btu_17_rated.unwrap_or(0.0) * qty;
-
-            let btu_95_rated = data.btu_95_rated.unwrap_or(0.0);
-            let hspf = data.hspf.unwrap_or(0.0);
-            let seer = data.seer.unwrap_or(0.0);
-
-            totals.total_btu_95_rated += btu_95_rated * qty;
-            totals.weighted_hspf_sum += btu_95_rated * hspf * qty;
-            totals.weighted_seer_sum += btu_95_rated * seer * qty;
-
-            table.add_row(vec![
-                Cell::new(&data.model_number),
-                Cell::new(count).set_alignment(CellAlignment::Center),
-                Cell::new(&ahri).set_alignment(CellAlignment::Center),
-                Cell::new(format!("{:.0}", btu_95_min * qty)).set_alignment(CellAlignment::Right),
-                Cell::new(format!("{:.0}", btu_design_max * qty)).set_alignment(CellAlignment::Right),
-            ]);
+    // --data-file（以及 check-project --baseline）的格式；内置目录不使用
+    pub(crate) fn catalog_format(&self) -> CatalogFormat {
+        CatalogFormat {
+            delimiter: self.csv_delimiter,
+            decimal_separator: self.decimal_separator,
+            range_basis: self.range_basis,
         }
     }
 
-    for (identifier, count) in not_found_inputs {
-        table.add_row(vec![
-            Cell::new(identifier).add_attribute(Attribute::Dim).fg(Color::Red), // 红色高亮
-            Cell::new(count).set_alignment(CellAlignment::Center),
-            Cell::new("NOT FOUND").set_alignment(CellAlignment::Center).fg(Color::Red),
-            Cell::new("-"),
-            Cell::new("-"),
-        ]);
+    // --schema 每次读取目录文件时重新读取，serve 的 /reload 也能用上修改后的列定义
+    pub(crate) fn catalog_schema(&self) -> Result<Option<CatalogSchema>, LcError> {
+        self.schema.as_deref().map(CatalogSchema::load).transpose()
     }
 
-    println!("{table}");
-    totals
+    // --format-table 和 --no-color；所有创建表格的函数都从这里取样式，NO_COLOR 与 --no-color 效果相同
+    pub(crate) fn table_options(&self) -> TableOptions {
+        TableOptions {
+            style: self.format_table.unwrap_or_default(),
+            no_color: self.no_color || std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()),
+        }
+    }
 }
 
-fn print_summary_table(totals: &CalculationTotals, design_temp: f64) {
-    let mut table = create_styled_table();
-
-    let mut add_summary_row = |label: &str, value: f64| {
-        table.add_row(vec![
-            Cell::new(label),
-            Cell::new(format!("{:.0}", value)).set_alignment(CellAlignment::Right),
-        ]);
-    };
-
-    add_summary_row("Btu @95 min", totals.total_btu_95_min);
-    add_summary_row("Btu @5  max", totals.total_btu_5_max);
-    add_summary_row("Btu @17 max", totals.total_btu_17_max);
-    add_summary_row("Btu @17 rtd", totals.total_btu_17_rated);
-    add_summary_row(&format!("Btu @{} max", design_temp), totals.total_btu_design_max);
-    add_summary_row("Design Temp", design_temp);
-
-    println!("{table}");
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuoteFormat {
+    Table,
+    Markdown,
+    Html,
 }
 
-fn print_recommendation(totals: &CalculationTotals) {
-    let max_val = totals.total_btu_design_max;
-    let mid_val = max_val / 1.1;
-    let min_val = max_val / 1.2;
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(exit_code(e.as_ref()))
+        }
+    }
+}
 
-    println!("\n Recommend range: {:.0} - {:.0} - {:.0}", min_val, mid_val, max_val);
+// 库错误按变体区分退出码：输入错误与 clap 的参数错误一样为 2，目录读取失败为 3，其余为 1
+fn exit_code(error: &(dyn std::error::Error + 'static)) -> u8 {
+    match error.downcast_ref::<LcError>() {
+        Some(
+            LcError::InvalidQuantity { .. }
+            | LcError::TooManyModels { .. }
+            | LcError::InvalidDesignTemp(_)
+            | LcError::AmbiguousAggregationKey { .. },
+        ) => 2,
+        Some(LcError::DataLoad { .. }) => 3,
+        Some(LcError::UnknownIdentifier { .. } | LcError::UnknownIdentifiers { .. }) | None => 1,
+    }
 }
 
-// --- Add: BHL/SF or BH/SF Analysis ---
-// 恢复 design_temp 参数以显示完整公式
-fn print_area_metrics(area: f64, totals: &CalculationTotals) {
-    print_section_title("BHL/SF or BH/SF ANALYSIS", Color::Magenta);
-    println!(" {:.0} sq ft", area);
-
-    // 1. User Result
-    let bhl_sf = if area > 0.0 { totals.total_btu_design_max / area } else { 0.0 };
-    let bh_sf = if area > 0.0 { totals.total_btu_17_rated / area } else { 0.0 };
-
-    let mut result_table = create_styled_table();
-    // result_table.set_header(vec![
-    //     Cell::new("Metric").add_attribute(Attribute::Bold),
-    //     Cell::new("Formula").add_attribute(Attribute::Bold),
-    //     Cell::new("Your Value").add_attribute(Attribute::Bold),
-    // ]);
-    
-    result_table.add_row(vec![
-        Cell::new("RES BHL/SF").fg(Color::Green).add_attribute(Attribute::Bold),
-        // Cell::new(format!("Max Btu @ {} / Area", design_temp)),
-        Cell::new(format!("{:.2}", bhl_sf)).add_attribute(Attribute::Bold),
-    ]);
-    result_table.add_row(vec![
-        Cell::new("SMB BH/SF").fg(Color::Cyan).add_attribute(Attribute::Bold),
-        // Cell::new("Rated Btu @ 17 / Area"),
-        Cell::new(format!("{:.2}", bh_sf)).add_attribute(Attribute::Bold),
-    ]);
-    println!("{result_table}");
-
-    // 2. Combined Reference Table (合并参考表格)
-    print_section_title("Con Edison Recommended Range", Color::DarkGrey);
-    
-    let mut ref_table = create_styled_table();
-    ref_table.set_header(vec![
-        Cell::new("Type").fg(Color::DarkGrey),
-        Cell::new("Category / Year").fg(Color::DarkGrey),
-        Cell::new("Min").fg(Color::DarkGrey),
-        Cell::new("Max").fg(Color::DarkGrey)
-    ]);
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches)?;
+    apply_config_file(&mut cli, &matches)?;
+    let _ = LOG_FORMAT.set(cli.log_format);
+    let table_options = cli.table_options();
+
+    // 这几个子命令不需要读取目录，目录路径配置错误时也能用来修正
+    match &cli.command {
+        Some(Command::Config { action }) => return run_config(action.as_ref(), table_options),
+        Some(Command::History { action, limit }) => return print_history(action.as_ref(), *limit, cli.precision, table_options),
+        Some(Command::Man { out_dir }) => return write_man_pages(out_dir.as_deref()),
+        Some(Command::Template { format: TemplateFormat::Csv }) => {
+            print!("{}", catalog_template());
+            return Ok(());
+        }
+        _ => {}
+    }
     
-    // Residential Data
-    let res_data = vec![
-        ("Pre-1945 (Uninsulated)", "30", "45"),
-        ("Pre-1945 (Insulated)", "25", "45"),
-        ("Pre-1979", "20", "35"),
-        ("1979-2006", "15", "30"),
-        ("2007 or later", "15", "25"),
-    ];
-
-    for (i, (year, min, max)) in res_data.iter().enumerate() {
-        let type_cell = if i == 0 { Cell::new("Residential").fg(Color::Green) } else { Cell::new("") };
-        ref_table.add_row(vec![
-            type_cell,
-            Cell::new(year).fg(Color::DarkGrey),
-            Cell::new(min).fg(Color::DarkGrey),
-            Cell::new(max).fg(Color::DarkGrey),
-        ]);
+    if cli.check_csv_encoding
+        && let Some(path) = cli.data_file.as_deref()
+    {
+        check_csv_encoding(path)?;
     }
-
-    // SMB Data
-    let smb_data = vec![
-        ("Restaurant", "20", "30"),
-        ("Big Box Retail", "15", "35"),
-        ("Small Retail", "20", "40"),
-        ("Schools", "18", "35"),
-        ("Offices", "15", "30"),
-        ("Religious Institutions", "20", "35"),
-        ("Grocery Stores", "20", "35"),
-        ("Auto Repair", "25", "45"),
-        ("Hospital & Healthcare", "20", "40"),
-        ("Assembly", "20", "30"),
-        ("Fitness Centers", "20", "35"),
-        ("Warehouses", "8", "20"),
-        ("Light Industrial", "25", "50"),
-        ("Hotels", "15", "30"),
-    ];
-
-    for (i, (sector, min, max)) in smb_data.iter().enumerate() {
-        let type_cell = if i == 0 { Cell::new("SMB").fg(Color::Cyan) } else { Cell::new("") };
-        ref_table.add_row(vec![
-            type_cell,
-            Cell::new(sector).fg(Color::DarkGrey),
-            Cell::new(min).fg(Color::DarkGrey),
-            Cell::new(max).fg(Color::DarkGrey),
-        ]);
+    let schema = cli.catalog_schema()?;
+    if !cli.require_columns.is_empty() {
+        check_required_columns(cli.data_file.as_deref(), cli.catalog_format(), &cli.require_columns)?;
     }
-    println!("{ref_table}");
-}
-
-fn print_loan_metrics(totals: &CalculationTotals) {
-    print_section_title("Loan Energy Saving Calculator", Color::Yellow);
+    let mut machine_data_map = load_machine_data(cli.data_file.as_deref(), cli.catalog_format(), schema.as_ref(), cli.skip_placeholders, cli.strict)?;
+    for machine in &cli.virtual_machines {
+        machine_data_map.insert(machine.clone());
+    }
+    if let Some(Command::Alias { action }) = &cli.command {
+        return run_alias(&machine_data_map, action, table_options);
+    }
+    insert_aliases(&mut machine_data_map)?;
 
-    let avg_hspf = if totals.total_btu_95_rated > 0.0 {
-        (totals.weighted_hspf_sum / totals.total_btu_95_rated) * 0.9 - 0.00000000000002
-    } else {
-        0.0
-    };
+    if let Some(command) = &cli.command {
+        return match command {
+            Command::List { format } => {
+                print_catalog_rows(&machine_data_map.models().collect::<Vec<_>>(), *format, None, table_options)
+            }
+            Command::Search { pattern, regex, min_temp_rating, limit, format } => {
+                search_catalog(&machine_data_map, pattern.as_deref(), *regex, *min_temp_rating, *limit, *format, table_options)
+            }
+            Command::Info { identifier, format } => {
+                print_machine_info(&machine_data_map, identifier, cli.design_temp, *format, table_options)
+            }
+            Command::Interp { identifier, temp, format } => {
+                print_interpolated_value(&machine_data_map, identifier, *temp, cli.force_interpolation, *format)
+            }
+            Command::CurveCompare { models, from, to, step, format } => curve_compare(
+                &machine_data_map,
+                models,
+                *from,
+                *to,
+                *step,
+                cli.force_interpolation,
+                *format,
+            ),
+            Command::Ahri { number, format } => lookup_ahri(&machine_data_map, *number, *format, table_options),
+            Command::Suggest { load, max_oversize, target_oversize, format } => suggest_single_unit(
+                &machine_data_map,
+                *load,
+                cli.design_temp,
+                *max_oversize,
+                *target_oversize,
+                *format,
+                table_options,
+            ),
+            Command::Top { by, max_min_capacity, limit, format } => {
+                top_models(&machine_data_map, *by, *max_min_capacity, *limit, cli.design_temp, *format, table_options)
+            }
+            Command::Stats { prefix_len, bucket, format } => {
+                print_catalog_stats(&machine_data_map, usize::from(*prefix_len), *bucket, *format, table_options)
+            }
+            Command::Missing { only, format } => {
+                if print_missing_fields(&machine_data_map, *only, *format, table_options)? {
+                    std::process::exit(1);
+                }
+                Ok(())
+            }
+            Command::Codes { filter, reverse, format } => {
+                print_code_mapping(&machine_data_map, filter.as_deref(), *reverse, *format, table_options)
+            }
+            Command::Compare { first, second, format } => {
+                compare_machines(&machine_data_map, first, second, cli.design_temp, *format, table_options)
+            }
+            Command::CheckLoad { load, min_coverage, max_coverage, machines } => {
+                if !check_load(&cli, &machine_data_map, machines, *load, *min_coverage, *max_coverage)? {
+                    std::process::exit(1);
+                }
+                Ok(())
+            }
+            Command::Quote { machines, tax_rate, quote_number, title, allow_missing_prices, format } => {
+                let options = QuoteOptions {
+                    tax_rate: *tax_rate,
+                    quote_number: quote_number.as_deref(),
+                    title,
+                    allow_missing_prices: *allow_missing_prices,
+                    format: *format,
+                };
+                print_quote(&cli, &machine_data_map, machines, &options)
+            }
+            Command::Batch { source, output_dir, format, strict, summary } => {
+                if !run_batch(&cli, &machine_data_map, source, output_dir.as_deref(), *format, *strict, *summary)? {
+                    std::process::exit(1);
+                }
+                Ok(())
+            }
+            Command::RenameCheck { files, fix } => {
+                if rename_check(&machine_data_map, files, *fix)? {
+                    std::process::exit(1);
+                }
+                Ok(())
+            }
+            Command::WatchDir { dir, format, interval_ms, once } => {
+                watch_dir(&cli, &machine_data_map, dir, *format, *interval_ms, *once)
+            }
+            Command::CheckProject { file, baseline, format } => {
+                if check_project(&cli, &machine_data_map, file, baseline.as_deref(), *format)? {
+                    std::process::exit(1);
+                }
+                Ok(())
+            }
+            Command::Config { .. }
+            | Command::History { .. }
+            | Command::Man { .. }
+            | Command::Alias { .. }
+            | Command::Template { .. } => unreachable!("handled before loading the catalog"),
+            #[cfg(feature = "server")]
+            Command::Serve { port } => server::serve(&cli, machine_data_map, *port),
+            Command::Diff { first, second, format } => {
+                if diff_scenarios(&cli, &machine_data_map, first, second, *format)? {
+                    std::process::exit(1);
+                }
+                Ok(())
+            }
+        };
+    }
 
-    let avg_seer = if totals.total_btu_95_rated > 0.0 {
-        totals.weighted_seer_sum / totals.total_btu_95_rated
-    } else {
-        0.0
-    };
+    if cli.validate_db {
+        print_db_validation(&machine_data_map, table_options);
+        return Ok(());
+    }
 
-    let mut table = create_styled_table();
+    if cli.self_test {
+        return run_self_test(&machine_data_map);
+    }
+    let user_input_map = parse_user_input(&cli.machines, cli.max_models)?;
     
-    table.add_row(vec![
-        Cell::new("Btu@95 rtd"),
-        Cell::new(format!("{:.0}", totals.total_btu_95_rated)).set_alignment(CellAlignment::Right),
-    ]);
-    table.add_row(vec![
-        Cell::new("Btu@5  max"),
-        Cell::new(format!("{:.0}", totals.total_btu_5_max)).set_alignment(CellAlignment::Right),
-    ]);
-    table.add_row(vec![
-        Cell::new("Btu@17 max"),
-        Cell::new(format!("{:.0}", totals.total_btu_17_max)).set_alignment(CellAlignment::Right),
-    ]);
-    table.add_row(vec![
-        Cell::new("Avg HSPF").fg(Color::Yellow).add_attribute(Attribute::Bold),
-        Cell::new(format!("{:.1}", avg_hspf)).set_alignment(CellAlignment::Right).add_attribute(Attribute::Bold),
-    ]);
-    table.add_row(vec![
-        Cell::new("Avg SEER").fg(Color::Yellow).add_attribute(Attribute::Bold),
-        Cell::new(format!("{:.1}", avg_seer)).set_alignment(CellAlignment::Right).add_attribute(Attribute::Bold),
-    ]);
-
-    println!("{table}");
-}
+    let options = cli.calculation_options(&machine_data_map)?;
+    let mut result = perform_calculation(&user_input_map, &machine_data_map, &options)?;
+    if cli.list_unmatched {
+        // not_found 已按标识排序，且输入按标识合并过，不会重复
+        for missing in &result.not_found {
+            println!("{}", missing.identifier);
+        }
+        if !result.not_found.is_empty() {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if !result.not_found.is_empty() {
+        let missing: Vec<&str> = result.not_found.iter().map(|m| m.identifier.as_str()).collect();
+        match cli.on_not_found {
+            NotFoundAction::Warn if !cli.quiet_not_found => {
+                for identifier in &missing {
+                    diagnostics::warn("not_found", Some(identifier), format!("not found: {}", identifier));
+                }
+            }
+            NotFoundAction::Warn => {}
+            NotFoundAction::Error => return Err(unknown_identifiers_error(&machine_data_map, missing).into()),
+            NotFoundAction::Skip => result.not_found.clear(),
+        }
+    }
+    warn_extrapolation(&result, cli.design_temp);
+    warn_implausible_eer(&result);
+    if cli.warn_anomalies {
+        warn_capacity_anomalies(&result, cli.design_temp, cli.force_interpolation);
+    }
+    if cli.log_run {
+        log_run(&cli, &result, &user_input_map, &machine_data_map);
+    }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let cli = Cli::parse();
-    
-    let machine_data_map = load_machine_data()?;
-    let user_input_map = parse_user_input(&cli.machines).map_err(|e| e.to_string())?;
-    
-    let totals = perform_calculation(&user_input_map, &machine_data_map, cli.design_temp);
+    if cli.emit_shell_vars {
+        print_env_report(&result.totals, cli.design_temp, cli.required, true);
+        return Ok(());
+    }
+    match cli.output {
+        ReportFormat::Table => {}
+        ReportFormat::Json | ReportFormat::Yaml => {
+            let display_precision = cli.json_display.then_some(cli.precision);
+            return print_json_report(&result, cli.design_temp, cli.required, display_precision, cli.output);
+        }
+        ReportFormat::PlotlyJson => return print_plotly_json(&result, cli.design_temp),
+        ReportFormat::Env => {
+            print_env_report(&result.totals, cli.design_temp, cli.required, false);
+            return Ok(());
+        }
+        ReportFormat::OpenMetrics => {
+            print_openmetrics(&result, cli.design_temp, cli.required);
+            return Ok(());
+        }
+    }
+
+    let cop_temp = (cli.show_cop || cli.color_by_efficiency).then(|| cli.at_temp.unwrap_or(cli.design_temp));
+    let render_options = RenderOptions {
+        design_temp: cli.design_temp,
+        unit_label: &cli.capacity_unit_label,
+        cop_temp,
+        show_eer: cli.show_eer,
+        show_capacity_ratio: cli.show_capacity_ratio || cli.wide,
+        show_all_points: cli.show_all_points || cli.wide,
+        show_cert_date: cli.include_ahri_date,
+        quiet_ahri: cli.quiet_ahri,
+        quiet_not_found: cli.quiet_not_found,
+        precision: cli.precision,
+        show_code: cli.include_model_code || cli.wide,
+        color_by_efficiency: cli.color_by_efficiency,
+        group_prefix: cli.group_by_manufacturer.map(usize::from),
+        display_by: cli.display_by,
+        locale: cli.locale,
+        summary_order: cli.summary_order,
+        hide_zero_totals: cli.hide_zero_totals,
+        seasonal_cop: cli.include_seasonal_cop,
+        auto_compact: cli.format_table.is_none(),
+        table_options,
+    };
+    if !cli.no_header {
+        print_report_header();
+    }
+    if !cli.summary_only {
+        print!("{}", render_table(&result, &render_options));
+    }
 
-    print_summary_table(&totals, cli.design_temp);
-    print_recommendation(&totals);
+    print_summary_table(&result, &render_options);
+    if cli.show_missing_data_warning && !cli.summary_only {
+        print_missing_data_warnings(&result, cli.design_temp, table_options);
+    }
+    print_recommendation(&result, cli.precision);
+    let totals = &result.totals;
+    if cli.summary_only {
+        return Ok(());
+    }
+    if let Some(required) = cli.required {
+        print_requirement_check(&RequirementCheck::new(totals, required), cli.precision);
+    }
+    if let (Some(code_temp), Some(code_load)) = (cli.code_temp, cli.code_load) {
+        let code_totals = perform_calculation(&user_input_map, &machine_data_map, &options.clone().design_temp(code_temp))?
+        .totals;
+        let check = RequirementCheck::new(&code_totals, code_load);
+        print_code_compliance(&check, code_temp, code_totals.total_btu_design_max, cli.precision, table_options);
+    }
+    if let Some(design) = &cli.ashrae {
+        let mut totals_at = [0.0; 2];
+        for (total, temp) in totals_at.iter_mut().zip([design.heating_99, design.heating_97_5]) {
+            *total = perform_calculation(&user_input_map, &machine_data_map, &options.clone().design_temp(temp))?
+            .totals
+            .total_btu_design_max;
+        }
+        print_ashrae_totals(design, totals_at, &cli.capacity_unit_label, cli.precision, table_options);
+    }
 
     if let Some(area) = cli.area {
-        print_area_metrics(area, &totals);
+        print_area_metrics(area, totals, table_options);
     }
 
     if cli.loan {
-        print_loan_metrics(&totals);
+        print_loan_metrics(totals, cli.precision, table_options);
+    }
+
+    if cli.explain {
+        print_explanation(&result, cli.design_temp, cli.required, cli.precision, table_options);
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calculation_option_defaults_match_the_cli_defaults() {
+        // 直接读取 clap 的默认值，不经过解析，LC_DESIGN_TEMP 等环境变量不影响结果
+        let mut command = Cli::command();
+        // build 之后布尔开关才有默认值 "false"
+        command.build();
+        let default_of = |id: &str| {
+            let arg = command.get_arguments().find(|arg| arg.get_id() == id).unwrap();
+            arg.get_default_values().first().map(|value| value.to_string_lossy().into_owned())
+        };
+        let defaults = CalculationOptions::default();
+        assert_eq!(default_of("design_temp").unwrap().parse::<f64>().unwrap(), defaults.design_temp);
+        assert_eq!(AggregateBy::from_str(&default_of("aggregate_by").unwrap(), false).unwrap(), defaults.aggregate_by);
+        assert_eq!(SortBy::from_str(&default_of("sort").unwrap(), false).unwrap(), defaults.sort_by);
+        assert_eq!(default_of("force_interpolation").as_deref(), Some("false"));
+        assert!(!defaults.force_interpolation);
+        assert_eq!(default_of("derates"), None);
+        assert!(defaults.derates.is_empty());
+    }
+}
@@ -0,0 +1,1326 @@
+//! 报告输出：表格样式、明细表与汇总表、JSON/YAML/env 报告以及目录列表
+
+use std::io::IsTerminal;
+
+use clap::ValueEnum;
+use comfy_table::{Table, presets, Attribute, Cell, CellAlignment, Color, ContentArrangement};
+use regex::Regex;
+use serde::Serialize;
+
+use crate::calc::{
+    CalculationResult, CalculationTotals, InterpolationKind, NotFound, Recommendation, RequirementCheck, RowResult,
+    SeasonalCop, missing_data_warnings,
+};
+use crate::data::{MachineData, PLAUSIBLE_EER};
+use crate::diagnostics;
+use crate::input::AshraeDesign;
+
+/// 汇总表中各温度行的顺序（--summary-order）
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SummaryOrder {
+    /// 95 min, 5 max, 17 max, 17 rated, design max
+    Fixed,
+    /// Ascending rating temperature
+    Temperature,
+}
+
+/// 明细表 Model 列显示的标识（--display-by）；选中的标识缺失时改用另一个
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DisplayBy {
+    /// Catalog model number
+    Model,
+    /// Machine code
+    Code,
+}
+
+/// 明细表表头的语言（--locale）
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Locale {
+    /// English (default)
+    #[default]
+    En,
+    /// Español
+    Es,
+    /// 中文
+    Zh,
+    /// Français
+    Fr,
+}
+
+// 表头翻译：英文 → (es, zh, fr)；单位和温度（Btu@17 max 等）不翻译
+const HEADER_TRANSLATIONS: &[(&str, [&str; 3])] = &[
+    ("Model", ["Modelo", "型号", "Modèle"]),
+    ("Code", ["Código", "代码", "Code"]),
+    ("Qty", ["Cant.", "数量", "Qté"]),
+    ("AHRI#", ["AHRI#", "AHRI#", "AHRI#"]),
+    ("Cert. Date", ["Fecha cert.", "认证日期", "Date cert."]),
+    ("Derate", ["Reducción", "降额", "Déclassement"]),
+    ("Cap. Ratio", ["Rel. cap.", "容量比", "Rapport cap."]),
+];
+
+impl Locale {
+    /// 翻译表头；没有对应翻译时返回原文
+    pub fn header(self, english: &'static str) -> &'static str {
+        let index = match self {
+            Locale::En => return english,
+            Locale::Es => 0,
+            Locale::Zh => 1,
+            Locale::Fr => 2,
+        };
+        HEADER_TRANSLATIONS
+            .iter()
+            .find(|(key, _)| *key == english)
+            .map_or(english, |(_, translations)| translations[index])
+    }
+}
+
+/// 负荷计算报告的输出格式（--output）
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Styled tables (default)
+    Table,
+    /// One JSON object with rows, totals and recommendation
+    Json,
+    /// Plotly figure JSON with design-max and 95°F-min bar traces
+    PlotlyJson,
+    /// Same structure as `json`, as a YAML document
+    Yaml,
+    /// Shell `KEY=value` lines (LC_BTU_95_MIN=...) for `eval`/`source`
+    Env,
+    /// Prometheus/OpenMetrics text exposition (lc_btu_design_max{model="...",qty="..."} ...)
+    #[value(name = "openmetrics")]
+    OpenMetrics,
+}
+
+/// 表格边框样式（--format-table）
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TableStyle {
+    /// Box-drawing characters (default)
+    #[default]
+    Utf8,
+    /// +---+ borders
+    Ascii,
+    /// Dashes only, no vertical borders
+    Minimal,
+}
+
+/// 目录类子命令的输出格式
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Table,
+    Csv,
+    Json,
+    Markdown,
+}
+
+// 紧凑样式：无外边框、无行间分隔线，只保留表头分隔和列分隔
+const COMPACT_PRESET: &str = "     ═╪ ┆          ";
+const COMPACT_PRESET_ASCII: &str = "     =+ |          ";
+
+/// 表格外观选项，由调用方传给每个创建表格的函数（--format-table、--no-color）
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TableOptions {
+    pub style: TableStyle,
+    // 为 true 时不输出 ANSI 样式；命令行在 --no-color 或设置了 NO_COLOR 时打开
+    pub no_color: bool,
+}
+
+fn compact_preset(style: TableStyle) -> &'static str {
+    match style {
+        TableStyle::Utf8 => COMPACT_PRESET,
+        TableStyle::Ascii | TableStyle::Minimal => COMPACT_PRESET_ASCII,
+    }
+}
+
+// no_color 时不输出 ANSI 样式，但保留终端宽度用于自动换行
+fn apply_no_color(table: &mut Table, options: TableOptions) {
+    if options.no_color {
+        let width = table.width();
+        table.force_no_tty();
+        if let Some(width) = width {
+            table.set_width(width);
+        }
+    }
+}
+
+/// 辅助函数：统一创建表格样式 (更清爽的水平线风格)
+pub fn create_styled_table(options: TableOptions) -> Table {
+    let mut table = Table::new();
+    table.load_preset(match options.style {
+        TableStyle::Utf8 => presets::UTF8_FULL_CONDENSED,
+        TableStyle::Ascii => presets::ASCII_FULL_CONDENSED,
+        TableStyle::Minimal => presets::ASCII_HORIZONTAL_ONLY,
+    });
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+    apply_no_color(&mut table, options);
+    table
+}
+
+/// 按输出格式创建表格：markdown 使用管道表格且不带颜色
+/// 列宽按显示宽度计算（中日韩文字占两格），中文型号或单位标签也能对齐
+pub fn create_format_table(format: OutputFormat, options: TableOptions) -> Table {
+    if format == OutputFormat::Markdown {
+        let mut table = Table::new();
+        table.load_preset(presets::ASCII_MARKDOWN);
+        table.set_content_arrangement(ContentArrangement::Dynamic);
+        table.force_no_tty();
+        table
+    } else {
+        create_styled_table(options)
+    }
+}
+
+// 辅助函数：打印带颜色的分节标题
+pub(crate) fn print_section_title(title: &str, color: Color, options: TableOptions) {
+    print!("{}", section_title(title, color, options));
+}
+
+// 分节标题（前面空一行），以字符串返回以便拼进整段报告
+fn section_title(title: &str, color: Color, options: TableOptions) -> String {
+    let mut table = Table::new();
+    table.load_preset(presets::NOTHING);
+    apply_no_color(&mut table, options);
+    table.add_row(vec![
+        Cell::new(format!("=== {} ===", title)).add_attribute(Attribute::Bold).fg(color)
+    ]);
+    format!("\n{table}\n")
+}
+
+/// 表格报告的显示选项：明细表的列开关、单位标签、小数位数，以及汇总表的行顺序和附加行
+pub struct RenderOptions<'a> {
+    pub design_temp: f64,
+    pub unit_label: &'a str,
+    pub cop_temp: Option<f64>,
+    pub show_eer: bool,
+    // --show-capacity-ratio：单台设计温度最大制热量 ÷ Btu@47 max
+    pub show_capacity_ratio: bool,
+    // --show-all-points：设计温度列之后加上目录中每个制热数据点（数量 × 单台，未降额）
+    pub show_all_points: bool,
+    pub show_cert_date: bool,
+    // --quiet-ahri：所有行都没有 AHRI 编号时去掉 AHRI 列
+    pub quiet_ahri: bool,
+    pub quiet_not_found: bool,
+    pub precision: usize,
+    pub show_code: bool,
+    // 未指定时显示 MachineData::display_name（较短的 machine code 或 model number）
+    pub display_by: Option<DisplayBy>,
+    // --color-by-efficiency：COP 列按清单内的最低到最高 COP 从红到绿着色
+    pub color_by_efficiency: bool,
+    // --group-by-manufacturer：按型号前 N 个字符分组并加小计行
+    pub group_prefix: Option<usize>,
+    pub summary_order: SummaryOrder,
+    pub hide_zero_totals: bool,
+    // --include-seasonal-cop：汇总表附每台和系统的季节 COP 估算
+    pub seasonal_cop: bool,
+    pub locale: Locale,
+    // 没有显式指定 --format-table 时，终端放不下完整表格则自动改用紧凑样式
+    pub auto_compact: bool,
+    pub table_options: TableOptions,
+}
+
+/// 与命令行参数的默认值一致
+impl Default for RenderOptions<'_> {
+    fn default() -> Self {
+        RenderOptions {
+            design_temp: 17.0,
+            unit_label: "Btu",
+            cop_temp: None,
+            show_eer: false,
+            show_capacity_ratio: false,
+            show_all_points: false,
+            show_cert_date: false,
+            quiet_ahri: false,
+            quiet_not_found: false,
+            precision: 0,
+            show_code: false,
+            display_by: None,
+            color_by_efficiency: false,
+            group_prefix: None,
+            summary_order: SummaryOrder::Fixed,
+            hide_zero_totals: false,
+            seasonal_cop: false,
+            locale: Locale::En,
+            auto_compact: true,
+            table_options: TableOptions::default(),
+        }
+    }
+}
+
+/// 明细部分的完整输出：分节标题、明细表（完整样式放不下且允许时改用紧凑样式）以及外推脚注
+pub fn render_table(result: &CalculationResult, options: &RenderOptions) -> String {
+    let mut table = build_detail_table(result, options, false);
+
+    // 终端太窄放不下完整表格时，自动切换为紧凑样式并去掉 AHRI 列；用户指定的 --format-table 优先
+    if options.auto_compact
+        && std::io::stdout().is_terminal()
+        && let Some(terminal_width) = table.width()
+        && full_table_width(&table) > terminal_width as usize
+    {
+        table = build_detail_table(result, options, true);
+    }
+
+    let mut out = section_title("LOAD CALCULATION", Color::Blue, options.table_options);
+    out.push_str(&format!("{table}\n"));
+    if result.rows.iter().any(RowResult::is_extrapolated) {
+        out.push_str(" * extrapolated beyond the model's tested temperature range\n");
+    }
+    out
+}
+
+/// --warn-anomalies：设计温度所在区间内制热量随温度升高而下降（数据可疑）
+pub fn warn_capacity_anomalies(result: &CalculationResult, design_temp: f64, force_interpolation: bool) {
+    for row in &result.rows {
+        let Some(detail) = row.data.heating_capacity_detail(design_temp, force_interpolation) else { continue };
+        if matches!(detail.kind, InterpolationKind::Exact | InterpolationKind::SinglePoint) {
+            continue;
+        }
+        let [(t1, v1), (t2, v2)] = detail.bracket;
+        if t2 > t1 && v2 < v1 {
+            diagnostics::warn(
+                "capacity_anomaly",
+                Some(&row.key),
+                format!(
+                    "{}: heating capacity decreases with temperature between {}°F ({:.0}) and {}°F ({:.0})",
+                    row.key, t1, v1, t2, v2
+                ),
+            );
+        }
+    }
+}
+
+/// 设计温度超出型号数据范围、制热量靠外推得到时提醒（表格中只有 * 标记）
+pub fn warn_extrapolation(result: &CalculationResult, design_temp: f64) {
+    for row in result.rows.iter().filter(|row| row.is_extrapolated()) {
+        let points = row.data.heating_points();
+        let (Some(lowest), Some(highest)) = (points.first(), points.last()) else { continue };
+        diagnostics::warn(
+            "extrapolation",
+            Some(&row.key),
+            format!(
+                "{}: capacity at {}°F is extrapolated beyond the tested range ({}°F to {}°F)",
+                row.key, design_temp, lowest.0, highest.0
+            ),
+        );
+    }
+}
+
+/// 95°F EER 不合理（目录错列）的型号在明细中显示 "-" 且不计入项目 EER，这里说明原因
+pub fn warn_implausible_eer(result: &CalculationResult) {
+    for row in &result.rows {
+        let Some(eer) = row.data.implausible_eer_95_rated() else { continue };
+        diagnostics::warn(
+            "implausible_eer",
+            Some(&row.key),
+            format!(
+                "{}: EER@95 of {:.3} is outside {}..={} (check the Btu@95rated and kW@95rated columns); ignoring it",
+                row.key,
+                eer,
+                PLAUSIBLE_EER.start(),
+                PLAUSIBLE_EER.end()
+            ),
+        );
+    }
+}
+
+/// 报告抬头：版本号与生成时间，便于归档后辨认
+pub fn print_report_header() {
+    println!(
+        " lc {} | generated {}",
+        env!("CARGO_PKG_VERSION"),
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S %Z")
+    );
+}
+
+// 不换行时完整表格所需的宽度：内容 + 每列左右各 1 格留白 + 竖线
+fn full_table_width(table: &Table) -> usize {
+    let widths = table.column_max_content_widths();
+    widths.iter().map(|w| *w as usize + 2).sum::<usize>() + widths.len() + 1
+}
+
+fn build_detail_table(result: &CalculationResult, options: &RenderOptions, compact: bool) -> Table {
+    let RenderOptions {
+        design_temp,
+        unit_label,
+        cop_temp,
+        show_eer,
+        show_capacity_ratio,
+        show_all_points,
+        show_cert_date,
+        quiet_ahri,
+        quiet_not_found,
+        precision,
+        show_code,
+        display_by,
+        color_by_efficiency,
+        group_prefix,
+        locale,
+        table_options,
+        ..
+    } = *options;
+    let mut table = create_styled_table(table_options);
+    if compact {
+        table.load_preset(compact_preset(table_options.style));
+    }
+    let no_ahri_data = quiet_ahri && result.rows.iter().all(|row| row.data.ahri.is_none());
+    let show_ahri = !compact && !no_ahri_data;
+    let show_price = result.rows.iter().any(|row| row.price_per_kbtu.is_some());
+    let show_derate = result.rows.iter().any(|row| row.derate != 1.0);
+
+    let header_design_label = format!("{}@{} max", unit_label, design_temp);
+    let mut header = vec![Cell::new(locale.header("Model"))];
+    if show_code {
+        header.push(Cell::new(locale.header("Code")));
+    }
+    header.push(Cell::new(locale.header("Qty")));
+    if show_ahri {
+        header.push(Cell::new(locale.header("AHRI#")));
+    }
+    if show_cert_date {
+        header.push(Cell::new(locale.header("Cert. Date")));
+    }
+    header.push(Cell::new(format!("{}@95 min", unit_label)));
+    if show_derate {
+        header.push(Cell::new(locale.header("Derate")));
+    }
+    header.push(Cell::new(&header_design_label));
+    if show_all_points {
+        header.push(Cell::new(format!("{}@low max", unit_label)));
+        for temp in [5, 17, 47] {
+            header.push(Cell::new(format!("{}@{} max", unit_label, temp)));
+        }
+    }
+    if show_price {
+        header.push(Cell::new(format!("$/k{}@{}", unit_label, design_temp)));
+    }
+    if let Some(temp) = cop_temp {
+        header.push(Cell::new(format!("COP@{}", temp)));
+    }
+    if show_eer {
+        header.push(Cell::new("EER@95"));
+    }
+    if show_capacity_ratio {
+        header.push(Cell::new(locale.header("Cap. Ratio")));
+    }
+    table.set_header(header);
+
+    // 着色范围取清单中实际出现的最低和最高 COP
+    let cop_range = cop_temp.filter(|_| color_by_efficiency).and_then(|temp| {
+        let cops: Vec<f64> = result.rows.iter().filter_map(|row| row.data.cop_at_temp(temp)).collect();
+        let low = cops.iter().copied().reduce(f64::min)?;
+        let high = cops.iter().copied().reduce(f64::max)?;
+        Some((low, high))
+    });
+
+    let row_cells = |row: &RowResult| {
+        let name = match display_by {
+            None => row.data.display_name(),
+            Some(DisplayBy::Model) => row.data.model_number.clone(),
+            Some(DisplayBy::Code) => row.data.machine_code.clone().unwrap_or_else(|| row.data.model_number.clone()),
+        };
+        let mut cells = vec![Cell::new(name)];
+        if show_code {
+            cells.push(Cell::new(row.data.machine_code.as_deref().unwrap_or("-")));
+        }
+        cells.push(Cell::new(row.qty).set_alignment(CellAlignment::Center));
+        if show_ahri {
+            let ahri = row.data.ahri.map(|v| v.to_string()).unwrap_or("-".to_string());
+            cells.push(Cell::new(&ahri).set_alignment(CellAlignment::Center));
+        }
+        if show_cert_date {
+            let date = row.data.ahri_cert_date.as_deref().map(str::trim).filter(|d| !d.is_empty()).unwrap_or("-");
+            cells.push(Cell::new(date).set_alignment(CellAlignment::Center));
+        }
+        cells.push(Cell::new(format!("{:.*}", precision, row.btu_95_min)).set_alignment(CellAlignment::Right));
+        if show_derate {
+            let cell = Cell::new(format!("{:.2}", row.derate)).set_alignment(CellAlignment::Right);
+            cells.push(if row.derate != 1.0 { cell.fg(Color::Yellow) } else { cell });
+        }
+        // 外推得到的设计温度制热量加 * 并变暗，表格下方有脚注
+        if row.is_extrapolated() {
+            cells.push(
+                Cell::new(format!("{:.*}*", precision, row.btu_design_max))
+                    .set_alignment(CellAlignment::Right)
+                    .add_attribute(Attribute::Dim),
+            );
+        } else {
+            cells.push(Cell::new(format!("{:.*}", precision, row.btu_design_max)).set_alignment(CellAlignment::Right));
+        }
+        if show_all_points {
+            let qty = f64::from(row.qty);
+            let total = |value: Option<f64>| value.map(|v| format!("{:.*}", precision, v * qty)).unwrap_or("-".to_string());
+            let data = row.data;
+            let lowest = match (data.lowest_temp, data.btu_lowest_max.or(data.btu_lowest_rated)) {
+                (Some(temp), Some(value)) => format!("{} ({}°F)", total(Some(value)), temp),
+                _ => "-".to_string(),
+            };
+            cells.push(Cell::new(lowest).set_alignment(CellAlignment::Right));
+            for value in [data.btu_5_max, data.btu_17_max, data.btu_47_max] {
+                cells.push(Cell::new(total(value)).set_alignment(CellAlignment::Right));
+            }
+        }
+        if show_price {
+            let value = row.price_per_kbtu.map(|v| format!("{:.2}", v)).unwrap_or("-".to_string());
+            cells.push(Cell::new(value).set_alignment(CellAlignment::Right));
+        }
+        if let Some(temp) = cop_temp {
+            let cop = row.data.cop_at_temp(temp);
+            let value = cop.map(|v| format!("{:.2}", v)).unwrap_or("-".to_string());
+            let cell = Cell::new(value).set_alignment(CellAlignment::Right);
+            cells.push(match (cop, cop_range) {
+                (Some(cop), Some(range)) => cell.fg(efficiency_color(cop, range)),
+                _ => cell,
+            });
+        }
+        if show_eer {
+            let value = row.data.eer_95_rated().map(|v| format!("{:.2}", v)).unwrap_or("-".to_string());
+            cells.push(Cell::new(value).set_alignment(CellAlignment::Right));
+        }
+        if show_capacity_ratio {
+            let value = row.capacity_ratio().map(|v| format!("{:.2}", v)).unwrap_or("-".to_string());
+            cells.push(Cell::new(value).set_alignment(CellAlignment::Right));
+        }
+        cells
+    };
+    let not_found_cells = |missing: &NotFound| {
+        let mut cells = vec![Cell::new(&missing.identifier).add_attribute(Attribute::Dim).fg(Color::Red)]; // 红色高亮
+        if show_code {
+            cells.push(Cell::new("-"));
+        }
+        cells.extend([
+            Cell::new(missing.qty).set_alignment(CellAlignment::Center),
+            Cell::new("NOT FOUND").set_alignment(CellAlignment::Center).fg(Color::Red),
+            Cell::new("-"),
+        ]);
+        if show_ahri {
+            cells.push(Cell::new("-"));
+        }
+        if show_cert_date {
+            cells.push(Cell::new("-"));
+        }
+        if show_derate {
+            cells.push(Cell::new("-"));
+        }
+        if show_all_points {
+            cells.extend((0..4).map(|_| Cell::new("-")));
+        }
+        if show_price {
+            cells.push(Cell::new("-"));
+        }
+        if cop_temp.is_some() {
+            cells.push(Cell::new("-"));
+        }
+        if show_eer {
+            cells.push(Cell::new("-"));
+        }
+        if show_capacity_ratio {
+            cells.push(Cell::new("-"));
+        }
+        cells
+    };
+    let not_found: Vec<&NotFound> = result.not_found.iter().filter(|_| !quiet_not_found).collect();
+
+    let Some(prefix_len) = group_prefix else {
+        for row in &result.rows {
+            table.add_row(row_cells(row));
+        }
+        for missing in &not_found {
+            table.add_row(not_found_cells(missing));
+        }
+        return finish_detail_table(table, compact);
+    };
+
+    // 分组键取型号（未找到的取输入标识）前 N 个字符；组按出现顺序排列，组内保持原有顺序
+    let prefix = |text: &str| text.chars().take(prefix_len).collect::<String>();
+    let mut groups: Vec<(String, Vec<&RowResult>, Vec<&NotFound>)> = Vec::new();
+    let group_index = |key: String, groups: &mut Vec<(String, Vec<&RowResult>, Vec<&NotFound>)>| {
+        groups.iter().position(|g| g.0 == key).unwrap_or_else(|| {
+            groups.push((key, Vec::new(), Vec::new()));
+            groups.len() - 1
+        })
+    };
+    for row in &result.rows {
+        let index = group_index(prefix(&row.data.model_number), &mut groups);
+        groups[index].1.push(row);
+    }
+    for missing in not_found {
+        let index = group_index(prefix(&missing.identifier), &mut groups);
+        groups[index].2.push(missing);
+    }
+
+    for (key, rows, missing) in &groups {
+        for row in rows {
+            table.add_row(row_cells(row));
+        }
+        for missing in missing {
+            table.add_row(not_found_cells(missing));
+        }
+        let qty: u32 = rows.iter().map(|r| r.qty).sum::<u32>() + missing.iter().map(|m| m.qty).sum::<u32>();
+        let bold = |text: String| Cell::new(text).add_attribute(Attribute::Bold).set_alignment(CellAlignment::Right);
+        let mut cells = vec![Cell::new(format!("{}* subtotal", key)).add_attribute(Attribute::Bold)];
+        if show_code {
+            cells.push(Cell::new(""));
+        }
+        cells.push(bold(qty.to_string()).set_alignment(CellAlignment::Center));
+        if show_ahri {
+            cells.push(Cell::new(""));
+        }
+        if show_cert_date {
+            cells.push(Cell::new(""));
+        }
+        cells.push(bold(format!("{:.*}", precision, rows.iter().fold(0.0, |sum, r| sum + r.btu_95_min))));
+        if show_derate {
+            cells.push(Cell::new(""));
+        }
+        cells.push(bold(format!("{:.*}", precision, rows.iter().fold(0.0, |sum, r| sum + r.btu_design_max))));
+        if show_all_points {
+            cells.extend((0..4).map(|_| Cell::new("")));
+        }
+        if show_price {
+            cells.push(Cell::new(""));
+        }
+        if cop_temp.is_some() {
+            cells.push(Cell::new(""));
+        }
+        if show_eer {
+            cells.push(Cell::new(""));
+        }
+        if show_capacity_ratio {
+            cells.push(Cell::new(""));
+        }
+        table.add_row(cells);
+    }
+    finish_detail_table(table, compact)
+}
+
+// 红（最低 COP）→ 黄 → 绿（最高 COP）；终端声明支持 truecolor 时用 24 位颜色，否则取最接近的 256 色
+fn efficiency_color(cop: f64, (low, high): (f64, f64)) -> Color {
+    let t = if high > low { ((cop - low) / (high - low)).clamp(0.0, 1.0) } else { 0.5 };
+    let r = (255.0 * (2.0 - 2.0 * t).min(1.0)).round() as u8;
+    let g = (255.0 * (2.0 * t).min(1.0)).round() as u8;
+    let truecolor = std::env::var("COLORTERM").is_ok_and(|v| v == "truecolor" || v == "24bit");
+    if truecolor {
+        Color::Rgb { r, g, b: 0 }
+    } else {
+        let level = |c: u8| (f64::from(c) / 255.0 * 5.0).round() as u8;
+        Color::AnsiValue(16 + 36 * level(r) + 6 * level(g))
+    }
+}
+
+// 紧凑样式去掉单元格左侧留白
+fn finish_detail_table(mut table: Table, compact: bool) -> Table {
+    if compact {
+        for column in table.column_iter_mut() {
+            column.set_padding((0, 1));
+        }
+    }
+    table
+}
+
+/// Plotly Figure JSON：每台机器的设计温度最大制热量与 95°F 最小制冷量两组柱状图
+pub fn print_plotly_json(result: &CalculationResult, design_temp: f64) -> Result<(), Box<dyn std::error::Error>> {
+    let models: Vec<&str> = result.rows.iter().map(|row| row.key.as_str()).collect();
+    let design_max: Vec<f64> = result.rows.iter().map(|row| row.btu_design_max).collect();
+    let min_95: Vec<f64> = result.rows.iter().map(|row| row.btu_95_min).collect();
+
+    let figure = serde_json::json!({
+        "data": [
+            { "type": "bar", "name": format!("Btu@{} max", design_temp), "x": models, "y": design_max },
+            { "type": "bar", "name": "Btu@95 min", "x": models, "y": min_95 },
+        ],
+        "layout": {
+            "title": { "text": format!("Load calculation (design temp {}°F)", design_temp) },
+            "barmode": "group",
+            "xaxis": { "title": { "text": "Model" } },
+            "yaxis": { "title": { "text": "Btu/h" } },
+        },
+    });
+    println!("{}", serde_json::to_string_pretty(&figure)?);
+    Ok(())
+}
+
+// OpenMetrics 标签值转义：反斜杠、双引号和换行
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// OpenMetrics 文本格式：每行机器的容量（数量 × 单台）按 model/qty 标签输出，另加合计与推荐范围，以 `# EOF` 结尾
+pub fn print_openmetrics(result: &CalculationResult, design_temp: f64, required: Option<f64>) {
+    let mut out = String::new();
+    let mut family = |name: &str, help: &str, samples: Vec<(String, f64)>| {
+        out.push_str(&format!("# TYPE {} gauge\n# HELP {} {}\n", name, name, help));
+        for (labels, value) in samples {
+            out.push_str(&format!("{}{} {:.1}\n", name, labels, value));
+        }
+    };
+    let per_row = |value: fn(&RowResult) -> f64| -> Vec<(String, f64)> {
+        result
+            .rows
+            .iter()
+            .map(|row| (format!("{{model=\"{}\",qty=\"{}\"}}", escape_label(&row.key), row.qty), value(row)))
+            .collect()
+    };
+
+    let totals = &result.totals;
+    let range = Recommendation::from_totals(totals);
+    family("lc_design_temp_fahrenheit", "Design temperature in degrees Fahrenheit.", vec![(String::new(), design_temp)]);
+    family("lc_btu_design_max", "Maximum heating capacity at the design temperature, Btu/h, for all units of the row.", per_row(|row| row.btu_design_max));
+    family("lc_btu_95_min", "Minimum cooling capacity at 95F, Btu/h, for all units of the row.", per_row(|row| row.btu_95_min));
+    family("lc_total_btu_design_max", "Total maximum heating capacity at the design temperature, Btu/h.", vec![(String::new(), totals.total_btu_design_max)]);
+    family("lc_total_btu_95_min", "Total minimum cooling capacity at 95F, Btu/h.", vec![(String::new(), totals.total_btu_95_min)]);
+    family("lc_total_units", "Number of outdoor units.", vec![(String::new(), totals.total_units as f64)]);
+    family(
+        "lc_recommend_btu",
+        "Recommended heating load range, Btu/h.",
+        [("min", range.min), ("mid", range.mid), ("max", range.max)]
+            .into_iter()
+            .map(|(bound, value)| (format!("{{bound=\"{}\"}}", bound), value))
+            .collect(),
+    );
+    if let Some(required) = required {
+        let check = RequirementCheck::new(totals, required);
+        family("lc_required_btu", "Required heating load, Btu/h.", vec![(String::new(), check.required)]);
+        family(
+            "lc_meets_requirement",
+            "1 when the design capacity covers the required load, otherwise 0.",
+            vec![(String::new(), if check.meets_requirement { 1.0 } else { 0.0 })],
+        );
+    }
+    out.push_str("# EOF\n");
+    print!("{}", out);
+}
+
+/// 打印汇总表：各温度点的总制热量、设计温度、$/kBtu 以及可选的季节 COP 和项目 EER
+pub fn print_summary_table(result: &CalculationResult, options: &RenderOptions) {
+    let RenderOptions { design_temp, precision, summary_order: order, hide_zero_totals: hide_zero, show_eer, .. } = *options;
+    let totals = &result.totals;
+    let seasonal_cop = options.seasonal_cop.then(|| SeasonalCop::from_result(result));
+    let mut table = create_styled_table(options.table_options);
+
+    // (额定温度, 标签, 合计)
+    let mut rows = vec![
+        (95.0, "Btu @95 min".to_string(), totals.total_btu_95_min),
+        (5.0, "Btu @5  max".to_string(), totals.total_btu_5_max),
+        (17.0, "Btu @17 max".to_string(), totals.total_btu_17_max),
+        (17.0, "Btu @17 rtd".to_string(), totals.total_btu_17_rated),
+        (design_temp, format!("Btu @{} max", design_temp), totals.total_btu_design_max),
+    ];
+    if order == SummaryOrder::Temperature {
+        rows.sort_by(|a, b| a.0.total_cmp(&b.0));
+    }
+
+    for (_, label, value) in rows {
+        if hide_zero && value == 0.0 {
+            continue;
+        }
+        table.add_row(vec![
+            Cell::new(label),
+            Cell::new(format!("{:.*}", precision, value)).set_alignment(CellAlignment::Right),
+        ]);
+    }
+    table.add_row(vec![
+        Cell::new("Design Temp"),
+        Cell::new(design_temp).set_alignment(CellAlignment::Right),
+    ]);
+
+    if let Some(seasonal) = &seasonal_cop {
+        for (key, cop) in &seasonal.per_machine {
+            table.add_row(vec![
+                Cell::new(format!("Seasonal COP {}", key)),
+                Cell::new(format!("{:.1}", cop)).set_alignment(CellAlignment::Right),
+            ]);
+        }
+        let system = seasonal.system.map(|c| format!("{:.1}", c)).unwrap_or("-".to_string());
+        table.add_row(vec![
+            Cell::new("Estimated seasonal COP").add_attribute(Attribute::Bold),
+            Cell::new(system).set_alignment(CellAlignment::Right).add_attribute(Attribute::Bold),
+        ]);
+    }
+
+    if show_eer {
+        let eer = totals.project_eer().map(|v| format!("{:.2}", v)).unwrap_or("-".to_string());
+        table.add_row(vec![
+            Cell::new("Project EER @95").add_attribute(Attribute::Bold),
+            Cell::new(eer).set_alignment(CellAlignment::Right).add_attribute(Attribute::Bold),
+        ]);
+    }
+
+    if totals.total_price > 0.0 && totals.priced_btu_design_max > 0.0 {
+        table.add_row(vec![
+            Cell::new(format!("$/kBtu @{}", design_temp)),
+            Cell::new(format!("{:.2}", totals.total_price / (totals.priced_btu_design_max / 1000.0)))
+                .set_alignment(CellAlignment::Right),
+        ]);
+    }
+
+    println!("{table}");
+}
+
+/// 打印推荐负荷范围
+pub fn print_recommendation(result: &CalculationResult, precision: usize) {
+    let range = Recommendation::from_totals(&result.totals);
+
+    println!(
+        "\n Recommend range: {:.*} - {:.*} - {:.*}",
+        precision, range.min, precision, range.mid, precision, range.max
+    );
+}
+
+/// 打印 [`missing_data_warnings`] 的结果
+pub fn print_missing_data_warnings(result: &CalculationResult, design_temp: f64, table_options: TableOptions) {
+    print_section_title("MISSING DATA", Color::Yellow, table_options);
+    let warnings = missing_data_warnings(result, design_temp);
+    if warnings.is_empty() {
+        println!(" All machines have data at {}°F", design_temp);
+    }
+    for warning in warnings {
+        println!(" {}", warning);
+    }
+}
+
+/// --explain：按计算顺序写出每一步代入的数值（插值 → 降额 → 数量 → 合计 → 推荐范围 → 需求）
+pub fn print_explanation(
+    result: &CalculationResult,
+    design_temp: f64,
+    required: Option<f64>,
+    precision: usize,
+    table_options: TableOptions,
+) {
+    print_section_title("HOW THE NUMBERS WERE CALCULATED", Color::Cyan, table_options);
+    let num = |v: f64| format!("{:.*}", precision, v);
+    let temp = |t: f64| format!("{}°F", t);
+    // 公式中的负温度加括号：(10 - (-22))
+    let term = |t: f64| if t < 0.0 { format!("({})", t) } else { t.to_string() };
+
+    println!(" 1. Max heating capacity of one unit at {}", temp(design_temp));
+    for row in &result.rows {
+        let Some(detail) = row.capacity_detail else {
+            println!("    {}: no heating data in the catalog, counted as 0", row.key);
+            continue;
+        };
+        let [(t1, v1), (t2, v2)] = detail.bracket;
+        let step = match detail.kind {
+            InterpolationKind::Exact => format!("{} is a catalog point = {}", temp(t1), num(v1)),
+            InterpolationKind::SinglePoint => format!("only one catalog point ({}) = {}", temp(t1), num(v1)),
+            InterpolationKind::Interpolated | InterpolationKind::Extrapolated => {
+                let how = if detail.kind == InterpolationKind::Interpolated {
+                    format!("between {} and {}", temp(t1), temp(t2))
+                } else {
+                    format!("outside the catalog range, extending the line through {} and {}", temp(t1), temp(t2))
+                };
+                format!(
+                    "{}: {} + ({} - {}) × ({} - {}) / ({} - {}) = {}",
+                    how,
+                    num(v1),
+                    num(v2),
+                    num(v1),
+                    term(design_temp),
+                    term(t1),
+                    term(t2),
+                    term(t1),
+                    num(detail.value)
+                )
+            }
+        };
+        println!("    {}: {}", row.key, step);
+        let mut unit = detail.value;
+        if row.derate != 1.0 {
+            println!("      derate: {} × {} = {}", num(unit), row.derate, num(unit * row.derate));
+            unit *= row.derate;
+        }
+        println!("      quantity: {} × {} = {}", num(unit), row.qty, num(row.btu_design_max));
+    }
+    for missing in &result.not_found {
+        println!("    {}: not in the catalog, counted as 0", missing.identifier);
+    }
+
+    let totals = &result.totals;
+    let terms: Vec<String> = result.rows.iter().map(|row| num(row.btu_design_max)).collect();
+    println!("\n 2. Total at {}: {} = {}", temp(design_temp), terms.join(" + "), num(totals.total_btu_design_max));
+    let terms: Vec<String> = result.rows.iter().map(|row| num(row.btu_95_min)).collect();
+    println!("    Total Btu@95 min (catalog value × quantity): {} = {}", terms.join(" + "), num(totals.total_btu_95_min));
+
+    let range = Recommendation::from_totals(totals);
+    println!("\n 3. Recommended load range from the total");
+    println!("    min = {} / 1.2 = {}", num(totals.total_btu_design_max), num(range.min));
+    println!("    mid = {} / 1.1 = {}", num(totals.total_btu_design_max), num(range.mid));
+    println!("    max = total = {}", num(range.max));
+
+    if let Some(required) = required {
+        let check = RequirementCheck::new(totals, required);
+        println!("\n 4. Required load");
+        println!(
+            "    {} / {} × 100 = {:.0}% ({})",
+            num(totals.total_btu_design_max),
+            num(required),
+            check.oversize_pct,
+            if check.meets_requirement { "met" } else { "not met" }
+        );
+    }
+}
+
+/// 打印 --required 的检查结果
+pub fn print_requirement_check(check: &RequirementCheck, precision: usize) {
+    let verdict = if check.meets_requirement { "MET" } else { "NOT MET" };
+    println!(
+        " Required: {:.*} - {} ({:.0}% of required)",
+        precision, check.required, verdict, check.oversize_pct
+    );
+}
+
+/// 打印 --code-temp/--code-load 的合规检查：规范温度下的总制热量须覆盖 100% 的规范负荷
+pub fn print_code_compliance(
+    check: &RequirementCheck,
+    code_temp: f64,
+    capacity: f64,
+    precision: usize,
+    table_options: TableOptions,
+) {
+    print_section_title(&format!("CODE COMPLIANCE @{}°F", code_temp), Color::Cyan, table_options);
+    let (verdict, color) = if check.meets_requirement { ("COMPLIES", Color::Green) } else { ("DOES NOT COMPLY", Color::Red) };
+    let mut table = create_styled_table(table_options);
+    table.add_row(vec![Cell::new("Code load"), Cell::new(format!("{:.*}", precision, check.required))]);
+    table.add_row(vec![Cell::new(format!("Btu @{} max", code_temp)), Cell::new(format!("{:.*}", precision, capacity))]);
+    table.add_row(vec![Cell::new("Coverage"), Cell::new(format!("{:.0}%", check.oversize_pct))]);
+    table.add_row(vec![
+        Cell::new("Verdict").add_attribute(Attribute::Bold),
+        Cell::new(verdict).fg(color).add_attribute(Attribute::Bold),
+    ]);
+    println!("{table}");
+}
+
+/// --ashrae：99% 与 97.5% 两个冬季设计温度下的总最大制热量
+pub fn print_ashrae_totals(
+    design: &AshraeDesign,
+    totals: [f64; 2],
+    unit_label: &str,
+    precision: usize,
+    table_options: TableOptions,
+) {
+    let title = match design.city {
+        Some(city) => format!("ASHRAE DESIGN TEMPERATURES ({})", city),
+        None => "ASHRAE DESIGN TEMPERATURES".to_string(),
+    };
+    print_section_title(&title, Color::Cyan, table_options);
+    let mut table = create_styled_table(table_options);
+    table.set_header(vec!["Design", "Temp", &format!("{} max", unit_label)]);
+    for (label, temp, total) in [("99%", design.heating_99, totals[0]), ("97.5%", design.heating_97_5, totals[1])] {
+        table.add_row(vec![
+            Cell::new(label),
+            Cell::new(format!("{}°F", temp)).set_alignment(CellAlignment::Right),
+            Cell::new(format!("{:.*}", precision, total)).set_alignment(CellAlignment::Right),
+        ]);
+    }
+    println!("{table}");
+}
+
+/// `--output json` / `yaml` 以及 `lc serve` 返回的报告结构
+#[derive(Debug, Serialize)]
+pub struct JsonReport<'a> {
+    design_temp: f64,
+    rows: &'a [RowResult<'a>],
+    not_found: &'a [NotFound],
+    totals: &'a CalculationTotals,
+    recommendation: Recommendation,
+    #[serde(flatten)]
+    requirement: Option<RequirementCheck>,
+    // 与表格一致的四舍五入字符串；totals 等字段本身始终是未取整的 f64
+    #[serde(skip_serializing_if = "Option::is_none")]
+    display: Option<DisplayValues>,
+}
+
+#[derive(Debug, Serialize)]
+struct DisplayValues {
+    btu_95_min: String,
+    btu_95_rated: String,
+    btu_5_max: String,
+    btu_17_max: String,
+    btu_17_rated: String,
+    btu_design_max: String,
+    recommend_min: String,
+    recommend_mid: String,
+    recommend_max: String,
+}
+
+impl DisplayValues {
+    fn new(totals: &CalculationTotals, precision: usize) -> Self {
+        let round = |value: f64| format!("{:.*}", precision, value);
+        let range = Recommendation::from_totals(totals);
+        DisplayValues {
+            btu_95_min: round(totals.total_btu_95_min),
+            btu_95_rated: round(totals.total_btu_95_rated),
+            btu_5_max: round(totals.total_btu_5_max),
+            btu_17_max: round(totals.total_btu_17_max),
+            btu_17_rated: round(totals.total_btu_17_rated),
+            btu_design_max: round(totals.total_btu_design_max),
+            recommend_min: round(range.min),
+            recommend_mid: round(range.mid),
+            recommend_max: round(range.max),
+        }
+    }
+}
+
+impl<'a> JsonReport<'a> {
+    /// 由计算结果构建报告；给出 `required` 时附带需求检查
+    pub fn new(result: &'a CalculationResult<'a>, design_temp: f64, required: Option<f64>) -> Self {
+        JsonReport {
+            design_temp,
+            rows: &result.rows,
+            not_found: &result.not_found,
+            totals: &result.totals,
+            recommendation: Recommendation::from_totals(&result.totals),
+            requirement: required.map(|r| RequirementCheck::new(&result.totals, r)),
+            display: None,
+        }
+    }
+
+    fn with_display(mut self, precision: usize) -> Self {
+        self.display = Some(DisplayValues::new(self.totals, precision));
+        self
+    }
+}
+
+/// 以 JSON 或 YAML 打印报告；`display_precision` 为 Some 时附带与表格一致的取整字符串
+pub fn print_json_report(
+    result: &CalculationResult,
+    design_temp: f64,
+    required: Option<f64>,
+    display_precision: Option<usize>,
+    format: ReportFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut report = JsonReport::new(result, design_temp, required);
+    if let Some(precision) = display_precision {
+        report = report.with_display(precision);
+    }
+    match format {
+        ReportFormat::Yaml => print!("{}", serde_yaml::to_string(&report)?),
+        _ => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+    Ok(())
+}
+
+/// --output env：全部输出为整数，便于 shell 直接 eval/source
+/// `--output env` 每行一个变量；--emit-shell-vars 输出一行 `A=1; B=2`，设计温度保留一位小数
+pub fn print_env_report(totals: &CalculationTotals, design_temp: f64, required: Option<f64>, one_line: bool) {
+    let range = Recommendation::from_totals(totals);
+    let mut vars = vec![
+        ("LC_BTU_95_MIN", totals.total_btu_95_min),
+        ("LC_BTU_95_RATED", totals.total_btu_95_rated),
+        ("LC_BTU_5_MAX", totals.total_btu_5_max),
+        ("LC_BTU_17_MAX", totals.total_btu_17_max),
+        ("LC_BTU_17_RATED", totals.total_btu_17_rated),
+        ("LC_BTU_DESIGN_MAX", totals.total_btu_design_max),
+        ("LC_TOTAL_UNITS", totals.total_units as f64),
+        ("LC_BTU_PER_UNIT_AVG", totals.btu_per_unit_avg()),
+        ("LC_RECOMMEND_MIN", range.min),
+        ("LC_RECOMMEND_MID", range.mid),
+        ("LC_RECOMMEND_MAX", range.max),
+    ];
+    if let Some(required) = required {
+        let check = RequirementCheck::new(totals, required);
+        vars.push(("LC_REQUIRED", check.required));
+        vars.push(("LC_MEETS_REQUIREMENT", if check.meets_requirement { 1.0 } else { 0.0 }));
+        vars.push(("LC_OVERSIZE_PCT", check.oversize_pct));
+    }
+    let design_temp = if one_line { format!("{:.1}", design_temp) } else { (design_temp.round() as i64).to_string() };
+    let assignments: Vec<String> = std::iter::once(format!("LC_DESIGN_TEMP={}", design_temp))
+        .chain(vars.into_iter().map(|(name, value)| format!("{}={}", name, value.round() as i64)))
+        .collect();
+    println!("{}", assignments.join(if one_line { "; " } else { "\n" }));
+}
+
+/// 打印按面积计算的指标（BHL/SF、BH/SF）
+pub fn print_area_metrics(area: f64, totals: &CalculationTotals, table_options: TableOptions) {
+    print_section_title("BHL/SF or BH/SF ANALYSIS", Color::Magenta, table_options);
+    println!(" {:.0} sq ft", area);
+
+    // 1. User Result
+    let bhl_sf = if area > 0.0 { totals.total_btu_design_max / area } else { 0.0 };
+    let bh_sf = if area > 0.0 { totals.total_btu_17_rated / area } else { 0.0 };
+
+    let mut result_table = create_styled_table(table_options);
+    // result_table.set_header(vec![
+    //     Cell::new("Metric").add_attribute(Attribute::Bold),
+    //     Cell::new("Formula").add_attribute(Attribute::Bold),
+    //     Cell::new("Your Value").add_attribute(Attribute::Bold),
+    // ]);
+    
+    result_table.add_row(vec![
+        Cell::new("RES BHL/SF").fg(Color::Green).add_attribute(Attribute::Bold),
+        // Cell::new(format!("Max Btu @ {} / Area", design_temp)),
+        Cell::new(format!("{:.2}", bhl_sf)).add_attribute(Attribute::Bold),
+    ]);
+    result_table.add_row(vec![
+        Cell::new("SMB BH/SF").fg(Color::Cyan).add_attribute(Attribute::Bold),
+        // Cell::new("Rated Btu @ 17 / Area"),
+        Cell::new(format!("{:.2}", bh_sf)).add_attribute(Attribute::Bold),
+    ]);
+    println!("{result_table}");
+
+    // 2. Combined Reference Table (合并参考表格)
+    print_section_title("Con Edison Recommended Range", Color::DarkGrey, table_options);
+    
+    let mut ref_table = create_styled_table(table_options);
+    ref_table.set_header(vec![
+        Cell::new("Type").fg(Color::DarkGrey),
+        Cell::new("Category / Year").fg(Color::DarkGrey),
+        Cell::new("Min").fg(Color::DarkGrey),
+        Cell::new("Max").fg(Color::DarkGrey)
+    ]);
+    
+    // Residential Data
+    let res_data = [
+        ("Pre-1945 (Uninsulated)", "30", "45"),
+        ("Pre-1945 (Insulated)", "25", "45"),
+        ("Pre-1979", "20", "35"),
+        ("1979-2006", "15", "30"),
+        ("2007 or later", "15", "25"),
+    ];
+
+    for (i, (year, min, max)) in res_data.iter().enumerate() {
+        let type_cell = if i == 0 { Cell::new("Residential").fg(Color::Green) } else { Cell::new("") };
+        ref_table.add_row(vec![
+            type_cell,
+            Cell::new(year).fg(Color::DarkGrey),
+            Cell::new(min).fg(Color::DarkGrey),
+            Cell::new(max).fg(Color::DarkGrey),
+        ]);
+    }
+
+    // SMB Data
+    let smb_data = [
+        ("Restaurant", "20", "30"),
+        ("Big Box Retail", "15", "35"),
+        ("Small Retail", "20", "40"),
+        ("Schools", "18", "35"),
+        ("Offices", "15", "30"),
+        ("Religious Institutions", "20", "35"),
+        ("Grocery Stores", "20", "35"),
+        ("Auto Repair", "25", "45"),
+        ("Hospital & Healthcare", "20", "40"),
+        ("Assembly", "20", "30"),
+        ("Fitness Centers", "20", "35"),
+        ("Warehouses", "8", "20"),
+        ("Light Industrial", "25", "50"),
+        ("Hotels", "15", "30"),
+    ];
+
+    for (i, (sector, min, max)) in smb_data.iter().enumerate() {
+        let type_cell = if i == 0 { Cell::new("SMB").fg(Color::Cyan) } else { Cell::new("") };
+        ref_table.add_row(vec![
+            type_cell,
+            Cell::new(sector).fg(Color::DarkGrey),
+            Cell::new(min).fg(Color::DarkGrey),
+            Cell::new(max).fg(Color::DarkGrey),
+        ]);
+    }
+    println!("{ref_table}");
+}
+
+/// 目录列表中的一行（`lc list` / `lc search` 以及 `lc serve` 的 /models）
+#[derive(Debug, Serialize)]
+pub struct CatalogRow<'a> {
+    pub model_number: &'a str,
+    pub machine_code: Option<&'a str>,
+    pub ahri: Option<u64>,
+    pub btu_47_max: Option<f64>,
+    pub btu_5_max: Option<f64>,
+}
+
+impl<'a> From<&'a MachineData> for CatalogRow<'a> {
+    fn from(data: &'a MachineData) -> Self {
+        CatalogRow {
+            model_number: &data.model_number,
+            machine_code: data.machine_code.as_deref(),
+            ahri: data.ahri,
+            btu_47_max: data.btu_47_max,
+            btu_5_max: data.btu_5_max,
+        }
+    }
+}
+
+/// 数值取整显示，缺失时为 "-"
+pub fn format_optional_btu(value: Option<f64>) -> String {
+    value.map(|v| format!("{:.0}", v)).unwrap_or("-".to_string())
+}
+
+// 在终端输出中高亮匹配的文本
+fn highlight_matches(text: &str, pattern: Option<&Regex>) -> String {
+    match pattern {
+        Some(re) if std::io::stdout().is_terminal() => re
+            .replace_all(text, |caps: &regex::Captures| format!("\x1b[1;33m{}\x1b[0m", &caps[0]))
+            .into_owned(),
+        _ => text.to_string(),
+    }
+}
+
+/// 按格式打印目录行（表格、CSV、JSON 或 markdown）
+pub fn print_catalog_rows(
+    models: &[&MachineData],
+    format: OutputFormat,
+    highlight: Option<&Regex>,
+    table_options: TableOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let rows: Vec<CatalogRow> = models.iter().map(|data| CatalogRow::from(*data)).collect();
+
+    let highlight = highlight.filter(|_| format == OutputFormat::Table && !table_options.no_color);
+
+    match format {
+        OutputFormat::Table | OutputFormat::Markdown => {
+            let mut table = create_format_table(format, table_options);
+            table.set_header(vec![
+                Cell::new("Model"),
+                Cell::new("Code"),
+                Cell::new("AHRI#"),
+                Cell::new("Btu@47 max"),
+                Cell::new("Btu@5 max"),
+            ]);
+            for row in &rows {
+                let ahri = row.ahri.map(|v| v.to_string()).unwrap_or("-".to_string());
+                table.add_row(vec![
+                    Cell::new(highlight_matches(row.model_number, highlight)),
+                    Cell::new(highlight_matches(row.machine_code.unwrap_or("-"), highlight)).set_alignment(CellAlignment::Center),
+                    Cell::new(highlight_matches(&ahri, highlight)).set_alignment(CellAlignment::Center),
+                    Cell::new(format_optional_btu(row.btu_47_max)).set_alignment(CellAlignment::Right),
+                    Cell::new(format_optional_btu(row.btu_5_max)).set_alignment(CellAlignment::Right),
+                ]);
+            }
+            println!("{table}");
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(std::io::stdout());
+            for row in &rows {
+                writer.serialize(row)?;
+            }
+            writer.flush()?;
+        }
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&rows)?);
+        }
+    }
+    Ok(())
+}
+
+/// 打印贷款/补贴所需的指标（平均 HSPF、SEER 等）
+pub fn print_loan_metrics(totals: &CalculationTotals, precision: usize, table_options: TableOptions) {
+    print_section_title("Loan Energy Saving Calculator", Color::Yellow, table_options);
+
+    let avg_hspf = if totals.total_btu_95_rated > 0.0 {
+        (totals.weighted_hspf_sum / totals.total_btu_95_rated) * 0.9 - 0.00000000000002
+    } else {
+        0.0
+    };
+
+    let avg_seer = if totals.total_btu_95_rated > 0.0 {
+        totals.weighted_seer_sum / totals.total_btu_95_rated
+    } else {
+        0.0
+    };
+
+    let mut table = create_styled_table(table_options);
+    
+    table.add_row(vec![
+        Cell::new("Btu@95 rtd"),
+        Cell::new(format!("{:.*}", precision, totals.total_btu_95_rated)).set_alignment(CellAlignment::Right),
+    ]);
+    table.add_row(vec![
+        Cell::new("Btu@5  max"),
+        Cell::new(format!("{:.*}", precision, totals.total_btu_5_max)).set_alignment(CellAlignment::Right),
+    ]);
+    table.add_row(vec![
+        Cell::new("Btu@17 max"),
+        Cell::new(format!("{:.*}", precision, totals.total_btu_17_max)).set_alignment(CellAlignment::Right),
+    ]);
+    table.add_row(vec![
+        Cell::new("Avg HSPF").fg(Color::Yellow).add_attribute(Attribute::Bold),
+        Cell::new(format!("{:.1}", avg_hspf)).set_alignment(CellAlignment::Right).add_attribute(Attribute::Bold),
+    ]);
+    table.add_row(vec![
+        Cell::new("Avg SEER").fg(Color::Yellow).add_attribute(Attribute::Bold),
+        Cell::new(format!("{:.1}", avg_seer)).set_alignment(CellAlignment::Right).add_attribute(Attribute::Bold),
+    ]);
+
+    println!("{table}");
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use unicode_width::UnicodeWidthStr;
+
+    use super::*;
+    use crate::calc::{CalculationOptions, perform_calculation};
+    use crate::data::MachineDatabase;
+
+    #[test]
+    fn cjk_model_labels_keep_detail_columns_aligned() {
+        let machine = MachineData {
+            model_number: "热泵KM18".to_string(),
+            btu_95_min: Some(7000.0),
+            btu_17_max: Some(16400.0),
+            btu_47_max: Some(20000.0),
+            ..Default::default()
+        };
+        let machine_data = MachineDatabase::from_iter([machine]);
+        let user_input = HashMap::from([("热泵KM18".to_string(), 2), ("未知型号".to_string(), 1)]);
+        let result = perform_calculation(&user_input, &machine_data, &CalculationOptions::default()).unwrap();
+        let options = RenderOptions { unit_label: "英热", ..Default::default() };
+
+        for format in [OutputFormat::Table, OutputFormat::Markdown] {
+            let mut table = build_detail_table(&result, &options, false);
+            if format == OutputFormat::Markdown {
+                table.load_preset(presets::ASCII_MARKDOWN);
+            }
+            table.force_no_tty();
+            let rendered = table.to_string();
+            let widths: Vec<usize> = rendered.lines().map(UnicodeWidthStr::width).collect();
+            assert!(widths.iter().all(|w| *w == widths[0]), "misaligned rows:\n{rendered}");
+            assert!(rendered.contains("热泵KM18") && rendered.contains("英热@17 max"));
+        }
+    }
+
+    #[test]
+    fn locale_translates_known_headers_only() {
+        assert_eq!(Locale::Es.header("Model"), "Modelo");
+        assert_eq!(Locale::Es.header("Qty"), "Cant.");
+        assert_eq!(Locale::Zh.header("AHRI#"), "AHRI#");
+        assert_eq!(Locale::Fr.header("Qty"), "Qté");
+        assert_eq!(Locale::En.header("Cap. Ratio"), "Cap. Ratio");
+        assert_eq!(Locale::Es.header("EER@95"), "EER@95");
+        assert!(HEADER_TRANSLATIONS.iter().all(|(_, t)| t.iter().all(|h| !h.is_empty())));
+    }
+
+    #[test]
+    fn quiet_ahri_drops_the_column_only_when_every_row_lacks_a_number() {
+        let uncertified = MachineData { model_number: "AAA18".to_string(), btu_17_max: Some(15000.0), ..Default::default() };
+        let certified = MachineData { model_number: "BBB24".to_string(), ahri: Some(1002), ..uncertified.clone() };
+        let machine_data = MachineDatabase::from_iter([uncertified, certified]);
+        let options = RenderOptions { quiet_ahri: true, ..Default::default() };
+        let header = |models: &[&str]| {
+            let user_input = models.iter().map(|m| (m.to_string(), 1)).collect();
+            let result = perform_calculation(&user_input, &machine_data, &CalculationOptions::default()).unwrap();
+            build_detail_table(&result, &options, false).to_string().lines().nth(1).unwrap().to_string()
+        };
+        assert!(!header(&["AAA18"]).contains("AHRI#"));
+        assert!(header(&["AAA18", "BBB24"]).contains("AHRI#"));
+    }
+
+    #[test]
+    fn model_column_defaults_to_the_display_name() {
+        let machine = MachineData {
+            model_number: "KW09XQ19SDO".to_string(),
+            machine_code: Some("0919O".to_string()),
+            btu_17_max: Some(9600.0),
+            ..Default::default()
+        };
+        let machine_data = MachineDatabase::from_iter([machine]);
+        let user_input = HashMap::from([("KW09XQ19SDO".to_string(), 1)]);
+        let result = perform_calculation(&user_input, &machine_data, &CalculationOptions::default()).unwrap();
+        let model_cell = |display_by| {
+            let options = RenderOptions { display_by, ..Default::default() };
+            build_detail_table(&result, &options, false).to_string().lines().nth(3).unwrap().to_string()
+        };
+        assert!(model_cell(None).contains("0919O") && !model_cell(None).contains("KW09XQ19SDO"));
+        assert!(model_cell(Some(DisplayBy::Model)).contains("KW09XQ19SDO"));
+    }
+}
@@ -0,0 +1,133 @@
+// `lc serve`：本地 HTTP API，供估价网页调用
+//
+//   GET  /models     目录（与 `lc list --format json` 相同）
+//   POST /calculate  {"machines": ["KM18H5Ox2", ...], "design_temp": 5}，返回与 `--output json` 相同的结构
+//   POST /reload     重新读取 --data-file
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use load_calculation::calc::{AggregateBy, SortBy, perform_calculation};
+use load_calculation::data::MachineDatabase;
+use load_calculation::diagnostics;
+use load_calculation::input::{MachineSpec, parse_user_input};
+use load_calculation::render::{CatalogRow, JsonReport};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::Cli;
+use crate::config::insert_aliases;
+
+#[derive(Debug, Deserialize)]
+struct CalculateRequest {
+    machines: Vec<String>,
+    design_temp: Option<f64>,
+    required: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+struct TokenError {
+    token: String,
+    message: String,
+}
+
+type HttpResponse = Response<std::io::Cursor<Vec<u8>>>;
+
+fn json_response<T: Serialize>(status: u16, body: &T) -> HttpResponse {
+    let text = serde_json::to_string_pretty(body).unwrap_or_else(|e| format!("{{\"error\":\"{}\"}}", e));
+    let header = Header::from_bytes("Content-Type", "application/json").expect("static header");
+    Response::from_string(text).with_status_code(status).with_header(header)
+}
+
+fn error_response(status: u16, message: &str) -> HttpResponse {
+    json_response(status, &serde_json::json!({ "error": message }))
+}
+
+// --virtual 机器和配置文件中的别名；重新读取目录文件后需要再加一次
+fn add_cli_machines(cli: &Cli, machine_data: &mut MachineDatabase) -> Result<(), Box<dyn std::error::Error>> {
+    for machine in &cli.virtual_machines {
+        machine_data.insert(machine.clone());
+    }
+    insert_aliases(machine_data)
+}
+
+fn reload_catalog(cli: &Cli, path: &Path, machine_data: &mut MachineDatabase) -> Result<(), Box<dyn std::error::Error>> {
+    machine_data.reload_from(path, cli.catalog_format(), cli.catalog_schema()?.as_ref(), cli.skip_placeholders, cli.strict)?;
+    add_cli_machines(cli, machine_data)
+}
+
+fn calculate(cli: &Cli, machine_data: &MachineDatabase, request: &mut Request) -> HttpResponse {
+    let mut body = String::new();
+    if let Err(e) = request.as_reader().read_to_string(&mut body) {
+        return error_response(400, &format!("cannot read request body: {}", e));
+    }
+    let payload: CalculateRequest = match serde_json::from_str(&body) {
+        Ok(payload) => payload,
+        Err(e) => return error_response(400, &format!("invalid request: {}", e)),
+    };
+    if payload.machines.is_empty() {
+        return error_response(400, "machines must not be empty");
+    }
+
+    // 逐个 token 解析，一次返回所有格式错误
+    let errors: Vec<TokenError> = payload
+        .machines
+        .iter()
+        .filter_map(|token| {
+            token
+                .parse::<MachineSpec>()
+                .err()
+                .map(|e| TokenError { token: token.clone(), message: e.to_string() })
+        })
+        .collect();
+    if !errors.is_empty() {
+        return json_response(400, &serde_json::json!({ "errors": errors }));
+    }
+
+    let user_input = match parse_user_input(&payload.machines, cli.max_models) {
+        Ok(input) => input,
+        Err(e) => return error_response(400, &e.to_string()),
+    };
+    let design_temp = payload.design_temp.unwrap_or(cli.design_temp);
+    let options = match cli.calculation_options(machine_data) {
+        Ok(options) => options.design_temp(design_temp).aggregate_by(AggregateBy::Model).sort_by(SortBy::Model),
+        Err(e) => return error_response(400, &e.to_string()),
+    };
+    let result = match perform_calculation(&user_input, machine_data, &options) {
+        Ok(result) => result,
+        Err(e) => return error_response(400, &e.to_string()),
+    };
+    json_response(200, &JsonReport::new(&result, design_temp, payload.required))
+}
+
+pub(crate) fn serve(
+    cli: &Cli,
+    mut machine_data: MachineDatabase,
+    port: u16,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let server = Server::http(("127.0.0.1", port)).map_err(|e| format!("cannot listen on port {}: {}", port, e))?;
+    eprintln!("Listening on http://127.0.0.1:{} ({} models)", port, machine_data.len());
+
+    for mut request in server.incoming_requests() {
+        let path = request.url().split('?').next().unwrap_or_default().to_string();
+        let response = match (request.method(), path.as_str()) {
+            (Method::Get, "/models") => {
+                let rows: Vec<CatalogRow> = machine_data.models().map(CatalogRow::from).collect();
+                json_response(200, &rows)
+            }
+            (Method::Post, "/calculate") => calculate(cli, &machine_data, &mut request),
+            (Method::Post, "/reload") => match cli.data_file.as_deref() {
+                None => error_response(400, "reload is only available with --data-file"),
+                Some(path) => match reload_catalog(cli, path, &mut machine_data) {
+                    Ok(()) => json_response(200, &serde_json::json!({ "models": machine_data.len() })),
+                    Err(e) => error_response(500, &e.to_string()),
+                },
+            },
+            (_, "/models" | "/calculate" | "/reload") => error_response(405, "method not allowed"),
+            _ => error_response(404, "not found"),
+        };
+        if let Err(e) = request.respond(response) {
+            diagnostics::warn("server", None, format!("failed to send response: {}", e));
+        }
+    }
+    Ok(())
+}
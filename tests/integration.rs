@@ -0,0 +1,267 @@
+// 端到端测试：运行编译好的 lc 二进制，使用内置目录
+use std::process::{Command, Output};
+
+use load_calculation::calc::{CalculationOptions, perform_calculation};
+use load_calculation::data::{CatalogFormat, MachineData, load_machine_data};
+use load_calculation::input::{DEFAULT_MAX_MODELS, parse_user_input};
+use serde_json::Value;
+
+// 隔离用户环境：不读取真实配置文件，也不受 LC_* 环境变量影响
+fn lc(args: &[&str]) -> Output {
+    lc_with_env(args, &[])
+}
+
+// 额外设置的环境变量（例如把历史记录指向临时目录的 XDG_DATA_HOME）
+fn lc_with_env(args: &[&str], vars: &[(&str, &std::path::Path)]) -> Output {
+    let missing_config = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("no-such-config.toml");
+    Command::new(env!("CARGO_BIN_EXE_load-calculation"))
+        .args(args)
+        .env("LC_CONFIG", missing_config)
+        .env("NO_COLOR", "1")
+        .env_remove("LC_DESIGN_TEMP")
+        .env_remove("LC_DATA_FILE")
+        .env_remove("LC_LOG_RUN")
+        .envs(vars.iter().copied())
+        .output()
+        .expect("failed to run lc")
+}
+
+fn json_report(args: &[&str]) -> Value {
+    let output = lc(&[args, &["--output", "json"]].concat());
+    assert!(output.status.success(), "lc failed: {}", String::from_utf8_lossy(&output.stderr));
+    serde_json::from_slice(&output.stdout).expect("report is valid JSON")
+}
+
+fn assert_close(actual: &Value, expected: f64) {
+    let actual = actual.as_f64().expect("numeric value");
+    assert!((actual - expected).abs() < 0.01, "expected {expected}, got {actual}");
+}
+
+#[test]
+fn valid_input_exits_zero_with_report() {
+    let output = lc(&["KM18H5Ox1"]);
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("KM18H5O"));
+    assert!(stdout.contains("Recommend range"));
+}
+
+#[test]
+fn unknown_input_with_on_not_found_error_exits_one() {
+    let output = lc(&["--on-not-found", "error", "UNKNOWNA1", "UNKNOWNB1"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unknown models: UNKNOWNA, UNKNOWNB"));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn on_not_found_error_keeps_suggestions() {
+    let output = lc(&["--on-not-found", "error", "KM18H5Ox1", "KM18H5x1"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("KM18H5 (did you mean KM18H5O?)"));
+}
+
+#[test]
+fn unknown_input_warns_by_default() {
+    let output = lc(&["KM18H5Ox1", "UNKNOWNA1"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("warning: not found: UNKNOWNA"));
+}
+
+#[test]
+fn log_format_json_emits_one_object_per_warning() {
+    let output = lc(&["KM18H5Ox1", "UNKNOWNA1", "-t=-40", "--log-format", "json"]);
+    assert_eq!(output.status.code(), Some(0));
+    let warnings: Vec<Value> = String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("each stderr line is JSON"))
+        .collect();
+    let kinds: Vec<&str> = warnings.iter().map(|w| w["kind"].as_str().unwrap()).collect();
+    assert_eq!(kinds, ["not_found", "extrapolation"]);
+    assert_eq!(warnings[1]["level"], "warn");
+    assert_eq!(warnings[1]["model"], "KM18H5O");
+}
+
+#[test]
+fn totals_at_catalog_point_match_hand_calculation() {
+    // KM18H5O: 95 min 7000, 17°F max 16400；KW09HQ19SDO: 95 min 2000, 17°F max 9000
+    let report = json_report(&["KM18H5Ox2", "KW09HQ19SDO"]);
+    let totals = &report["totals"];
+    assert_close(&totals["total_btu_95_min"], 2.0 * 7000.0 + 2000.0);
+    assert_close(&totals["total_btu_design_max"], 2.0 * 16400.0 + 9000.0);
+    assert_eq!(totals["total_units"], 3);
+    assert_close(&report["recommendation"]["mid"], 41800.0 / 1.1);
+}
+
+#[test]
+fn totals_between_points_are_interpolated() {
+    // 10°F 在 5°F (13600) 与 17°F (16400) 之间：13600 + 2800 × 5/12
+    let report = json_report(&["KM18H5Ox2", "-t", "10"]);
+    assert_close(&report["totals"]["total_btu_design_max"], 2.0 * (13600.0 + 2800.0 * 5.0 / 12.0));
+}
+
+#[test]
+fn catalog_format_options_do_not_apply_to_the_embedded_catalog() {
+    // --decimal-separator / --csv-delimiter 只描述 --data-file，内置目录仍按 "," 和 "." 读取
+    let report = json_report(&["KM18H5Ox1", "--decimal-separator", "comma", "--csv-delimiter", ";"]);
+    assert_close(&report["totals"]["total_btu_design_max"], 16400.0);
+}
+
+#[test]
+fn diff_totals_use_the_same_options_as_a_report() {
+    let output = lc(&["--derate", "KM18H5O=0.5", "-t", "5", "diff", "--format", "json", "KM18H5Ox1", "--", "KM18H5Ox2"]);
+    assert_eq!(output.status.code(), Some(1));
+    let rows: Value = serde_json::from_slice(&output.stdout).expect("diff is valid JSON");
+    let design = rows.as_array().unwrap().iter().find(|row| row["name"] == "Btu @5 max").unwrap();
+    let report = json_report(&["--derate", "KM18H5O=0.5", "-t", "5", "KM18H5Ox2"]);
+    // 2 × 13600 × 0.5
+    assert_close(&report["totals"]["total_btu_design_max"], 13600.0);
+    assert_close(&design["b"], 13600.0);
+}
+
+#[test]
+fn invalid_design_temp_is_a_usage_error() {
+    let output = lc(&["KM18H5Ox1", "-t", "warm"]);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn input_and_catalog_errors_map_to_exit_codes() {
+    assert_eq!(lc(&["KM18H5Ox99999999999"]).status.code(), Some(2));
+    assert_eq!(lc(&["KM18H5Ox1", "-t", "NaN"]).status.code(), Some(2));
+    assert_eq!(lc(&["-t", "NaN", "info", "KM18H5O"]).status.code(), Some(2));
+    assert_eq!(lc(&["-t", "inf", "top", "--by", "design"]).status.code(), Some(2));
+    assert_eq!(lc(&["interp", "KM18H5O", "nan"]).status.code(), Some(2));
+    let output = lc(&["KM18H5Ox1", "--code-temp", "nan", "--code-load", "1000"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    assert_eq!(lc(&["curve-compare", "KM18H5O", "KW09HQ19SDO", "--from", "nan"]).status.code(), Some(2));
+    assert_eq!(lc(&["curve-compare", "KM18H5O", "KW09HQ19SDO", "--format", "csv", "--step", "inf"]).status.code(), Some(2));
+    let output = lc(&["curve-compare", "KM18H5O", "KW09HQ19SDO", "--format", "csv", "--step", "1e-300"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    assert_eq!(lc(&["KM18H5Ox1", "--show-cop", "--at-temp", "nan"]).status.code(), Some(2));
+    assert_eq!(lc(&["info", "XKM18H5O"]).status.code(), Some(1));
+    assert_eq!(lc(&["stats", "--bucket", "0.0001"]).status.code(), Some(1));
+    assert_eq!(lc(&["--require-columns", "Foo", "KM18H5Ox1"]).status.code(), Some(3));
+    assert_eq!(lc(&["--schema", "/nonexistent/schema.toml", "KM18H5Ox1"]).status.code(), Some(3));
+    let output = lc(&["--data-file", "/nonexistent/catalog.csv", "KM18H5Ox1"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("Error: /nonexistent/catalog.csv: "));
+}
+
+// 输出快照：与 tests/snapshots/<名称>.txt 比较，见 assert_snapshot
+const SNAPSHOTS: &[(&str, &[&str])] = &[
+    ("report", &["--no-header", "KM18H5Ox2", "KW09HQ19SDO"]),
+    (
+        "report_area_loan",
+        &["--no-header", "KM18H5Ox2", "KW09HQ19SDO", "-t", "5", "--area", "1500", "--loan", "--show-cop", "--show-eer"],
+    ),
+    ("report_grouped", &["--no-header", "KM18H5Ox2", "KW09HQ19SDO", "--group-by-manufacturer", "2"]),
+    ("report_display_by_code", &["--no-header", "KU36UHO", "60U2", "KW09HQ19SDO", "--display-by", "code"]),
+    ("report_wide", &["--no-header", "KM18H5Ox2", "KW09HQ19SDO", "--wide"]),
+    ("report_json", &["KM18H5Ox2", "KW09HQ19SDO", "--output", "json"]),
+    ("report_yaml", &["KM18H5Ox2", "KW09HQ19SDO", "--output", "yaml"]),
+    ("report_env", &["KM18H5Ox2", "KW09HQ19SDO", "--output", "env", "--required", "30000"]),
+    ("report_openmetrics", &["KM18H5Ox2", "KW09HQ19SDO", "--output", "openmetrics", "--required", "30000"]),
+    ("code_compliance", &["--no-header", "KM18H5Ox2", "--code-temp=-5", "--code-load", "25000"]),
+    ("ashrae", &["--no-header", "KM18H5Ox2", "KW09HQ19SDO", "--ashrae", "chicago"]),
+    ("list_markdown", &["list", "--format", "markdown"]),
+    ("explain", &["--no-header", "KM18H5Ox2", "-t=-5", "--explain"]),
+    ("info", &["info", "KM18H5O"]),
+    ("search", &["search", "KM18"]),
+    ("search_min_temp_rating", &["search", "36", "--min-temp-rating", "-13"]),
+    ("top", &["top", "--by", "btu-17-max"]),
+    ("stats", &["stats"]),
+    ("curve_compare", &["curve-compare", "KM18H5O", "KW09HQ19SDO"]),
+    ("compare", &["compare", "KM18H5O", "KW09HQ19SDO"]),
+    ("suggest", &["suggest", "--load", "20000"]),
+    ("check_load", &["check-load", "--load", "30000", "KM18H5Ox2"]),
+];
+
+// 与 tests/snapshots/<名称> 逐字节比较；设置 LC_UPDATE_SNAPSHOTS=1 时改为重新写入
+fn assert_snapshot(name: &str, source: &str, actual: &[u8]) {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots").join(name);
+    if std::env::var_os("LC_UPDATE_SNAPSHOTS").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read(&path).unwrap_or_else(|e| panic!("missing snapshot {}: {e}", path.display()));
+    assert!(
+        actual == expected,
+        "{} differs from {}:\n{}",
+        source,
+        path.display(),
+        String::from_utf8_lossy(actual)
+    );
+}
+
+#[test]
+fn output_matches_snapshots() {
+    for (name, args) in SNAPSHOTS {
+        let output = lc(args);
+        let mut actual = format!("exit: {:?}\n", output.status.code()).into_bytes();
+        actual.extend_from_slice(&output.stdout);
+        assert_snapshot(&format!("{name}.txt"), &format!("output of `lc {}`", args.join(" ")), &actual);
+    }
+}
+
+// 库类型的序列化结果是对外的数据格式：字段名不随目录 CSV 表头变化，空值省略
+#[test]
+fn serialized_forms_match_snapshots() {
+    let catalog = load_machine_data(None, CatalogFormat::default(), None, false, false).unwrap();
+    let records: Vec<&MachineData> = ["KM18H5O", "KW09HQ19SDO"].iter().map(|model| catalog.get(model).unwrap()).collect();
+    let json = serde_json::to_string_pretty(&records).unwrap() + "\n";
+    assert_snapshot("serialize_machine_data.json", "serialized MachineData", json.as_bytes());
+
+    let input = parse_user_input(&["KM18H5Ox2".to_string(), "KW09HQ19SDO".to_string(), "UNKNOWNA1".to_string()], DEFAULT_MAX_MODELS).unwrap();
+    let result = perform_calculation(&input, &catalog, &CalculationOptions::default()).unwrap();
+    let json = serde_json::to_string_pretty(&result).unwrap() + "\n";
+    assert_snapshot("serialize_calculation_result.json", "serialized CalculationResult", json.as_bytes());
+}
+
+#[test]
+fn history_skips_malformed_lines() {
+    let data_home = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("history-malformed");
+    let history = data_home.join("lc").join("history.jsonl");
+    let _ = std::fs::remove_file(&history);
+
+    let output = lc_with_env(&["--log-run", "KM18H5Ox2"], &[("XDG_DATA_HOME", &data_home)]);
+    assert_eq!(output.status.code(), Some(0));
+    let logged = std::fs::read_to_string(&history).unwrap();
+    std::fs::write(&history, format!("{{\"timestamp\": \"truncated\n{}", logged)).unwrap();
+
+    let output = lc_with_env(&["history"], &[("XDG_DATA_HOME", &data_home)]);
+    assert_eq!(output.status.code(), Some(0), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("KM18H5Ox2"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("line 1"));
+}
+
+#[test]
+fn rename_check_fix_never_overwrites_an_earlier_backup() {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("rename-check-backups");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let catalog = dir.join("catalog.csv");
+    std::fs::write(
+        &catalog,
+        "model number,machine code,AHRI,Btu@95min,Btu@lowest max,lowest temperature,Btu@5max,Btu@17max,Btu@17rated,Btu@47max,Btu@95rated,HSPF,SEER,Price,superseded_by\n\
+         OLD18,,1001,6000,8000,-13,12000,15000,14000,18000,18000,10,20,900,NEW18\n\
+         NEW18,,1002,6000,8000,-13,12000,15000,14000,18000,18000,10,20,900,\n",
+    )
+    .unwrap();
+    let scenario = dir.join("scenario.txt");
+    let catalog = catalog.to_str().unwrap();
+    let scenario_arg = scenario.to_str().unwrap();
+
+    for (run, original) in ["OLD18x2\n", "OLD18x3\n"].iter().enumerate() {
+        std::fs::write(&scenario, original).unwrap();
+        let output = lc(&["--data-file", catalog, "rename-check", "--fix", scenario_arg]);
+        assert_eq!(output.status.code(), Some(0), "{}", String::from_utf8_lossy(&output.stderr));
+        let backup = if run == 0 { dir.join("scenario.txt.bak") } else { dir.join("scenario.txt.bak.1") };
+        assert_eq!(std::fs::read_to_string(backup).unwrap(), *original);
+    }
+    assert_eq!(std::fs::read_to_string(dir.join("scenario.txt.bak")).unwrap(), "OLD18x2\n");
+    assert_eq!(std::fs::read_to_string(&scenario).unwrap(), "NEW18x3\n");
+}